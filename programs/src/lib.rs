@@ -1,8 +1,23 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::{
+    ed25519_program,
+    hash::{hash, hashv},
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+use anchor_lang::system_program;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{self, Mint, Token, TokenAccount, Transfer},
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::{
+                instruction::harvest_withheld_tokens_to_mint, TransferFeeAmount, TransferFeeConfig,
+            },
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+        onchain::invoke_transfer_checked,
+    },
+    token_interface::{self, CloseAccount, Mint, TokenAccount, TokenInterface},
 };
 
 declare_id!("5ggd1t1UMGWHyiTGKmSgftmWAqtJnt8RmBh447s3DN8");
@@ -11,12 +26,29 @@ declare_id!("5ggd1t1UMGWHyiTGKmSgftmWAqtJnt8RmBh447s3DN8");
 pub mod race_vault {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+    /// Initialize a vault for a mint
+    /// `vault_id` namespaces independent prize pools in the same mint; vault 0
+    /// keeps the original single-vault addresses
+    pub fn initialize(ctx: Context<Initialize>, vault_id: u64) -> Result<()> {
         let cfg = &mut ctx.accounts.config;
         cfg.authority = ctx.accounts.authority.key();
         cfg.mint = ctx.accounts.mint.key();
+        cfg.vault_id = vault_id;
+        cfg.accepted_mints = Vec::new();
         cfg.vault_signer_bump = ctx.bumps.vault_signer;
         cfg.paused = false;
+        cfg.pending_authority = Pubkey::default();
+        // All roles start with the initializing authority until delegated
+        cfg.operator = cfg.authority;
+        cfg.pauser = cfg.authority;
+        cfg.treasurer = cfg.authority;
+        cfg.reserve_ratio_bps = 0;
+        cfg.claim_window = 0;
+        cfg.receipt_close_horizon = 0;
+        cfg.voucher_signer = Pubkey::default();
+        cfg.points_rate = 0;
+        cfg.amounts_from_points = false;
+        cfg.season_id = 0;
         
         // Initialize global payout registry
         let global_registry = &mut ctx.accounts.global_payout_registry;
@@ -25,23 +57,309 @@ pub mod race_vault {
         global_registry.total_payout_count = 0;
         global_registry.total_recipient_count = 0;
         global_registry.last_updated = Clock::get()?.unix_timestamp;
+
+        // Initialize global referral registry
+        let global_referral_registry = &mut ctx.accounts.global_referral_registry;
+        global_referral_registry.total_pending = 0;
+        global_referral_registry.total_claimed = 0;
+        global_referral_registry.total_bonus_count = 0;
+        global_referral_registry.total_referrer_count = 0;
+        global_referral_registry.last_updated = Clock::get()?.unix_timestamp;
         
         Ok(())
     }
 
-    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
-        require!(amount > 0, VaultError::ZeroAmount);
-
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.depositor_token.to_account_info(),
-            to: ctx.accounts.vault_token.to_account_info(),
-            authority: ctx.accounts.depositor.to_account_info(),
+    /// Migrate an existing deployment (admin only)
+    /// Grows Config to the current layout and creates the global referral registry,
+    /// seeded from the ReferrerRegistry accounts passed as remaining accounts
+    /// (sorted by address, each at most once)
+    pub fn migrate(ctx: Context<Migrate>) -> Result<()> {
+        let config_info = ctx.accounts.config.to_account_info();
+
+        // Config.authority sits right after the discriminator in every layout
+        let stored_authority = {
+            let data = config_info.try_borrow_data()?;
+            require!(data.len() >= 8 + 32, VaultError::InvalidAuthority);
+            Pubkey::try_from(&data[8..40]).map_err(|_| VaultError::InvalidAuthority)?
         };
-        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
-        token::transfer(cpi_ctx, amount)?;
+        require!(
+            stored_authority == ctx.accounts.authority.key(),
+            VaultError::InvalidAuthority
+        );
+
+        // Grow the account; new trailing fields are zero-filled
+        let new_len = 8 + Config::SIZE;
+        if config_info.data_len() < new_len {
+            grow_account(
+                &ctx.accounts.authority.to_account_info(),
+                &config_info,
+                &ctx.accounts.system_program.to_account_info(),
+                new_len,
+            )?;
+        }
+
+        // Roles added after deployment default to the current authority
+        let mut config = Config::try_deserialize(&mut &config_info.try_borrow_data()?[..])?;
+        for role in [Role::Operator, Role::Pauser, Role::Treasurer] {
+            if config.role_holder(role) == Pubkey::default() {
+                config.set_role_holder(role, config.authority);
+            }
+        }
+        config.try_serialize(&mut &mut config_info.try_borrow_mut_data()?[..])?;
+
+        // Backfill global referral totals from existing referrer registries
+        let config_key = config_info.key();
+        let global_registry = &mut ctx.accounts.global_referral_registry;
+        let mut previous_key: Option<Pubkey> = None;
+        for registry_info in ctx.remaining_accounts.iter() {
+            require!(
+                previous_key.map_or(true, |previous| registry_info.key() > previous),
+                VaultError::InvalidMigrationAccount
+            );
+            previous_key = Some(registry_info.key());
+
+            require!(registry_info.owner == &crate::ID, VaultError::InvalidMigrationAccount);
+            let registry = ReferrerRegistry::try_deserialize(&mut &registry_info.try_borrow_data()?[..])?;
+            let (expected_key, _) = Pubkey::find_program_address(
+                &[b"referrer_registry", config_key.as_ref(), registry.referrer.as_ref()],
+                &crate::ID,
+            );
+            require!(registry_info.key() == expected_key, VaultError::InvalidMigrationAccount);
+
+            global_registry.total_pending = global_registry.total_pending.checked_add(registry.total_pending).ok_or(VaultError::Overflow)?;
+            global_registry.total_claimed = global_registry.total_claimed.checked_add(registry.total_claimed).ok_or(VaultError::Overflow)?;
+            global_registry.total_bonus_count = global_registry.total_bonus_count.checked_add(registry.bonus_count).ok_or(VaultError::Overflow)?;
+            global_registry.total_referrer_count += 1;
+        }
+        global_registry.last_updated = Clock::get()?.unix_timestamp;
+
+        emit!(MigrationEvent {
+            authority: stored_authority,
+            config_size: new_len as u32,
+            referrer_count: global_registry.total_referrer_count,
+            referral_pending: global_registry.total_pending,
+            timestamp: global_registry.last_updated,
+        });
+
+        Ok(())
+    }
+
+    /// Migrate one recipient's payout accounts from the pre-status layout (admin only)
+    /// remaining_accounts holds every legacy PayoutReceipt of the recipient, in
+    /// registration order. Legacy claims settled everything pending at once, so the
+    /// pending receipts are the newest ones and sum to the registry's total_pending;
+    /// receipts get their sequence and the registry its claimed_count from that.
+    /// Pending receipts expire one claim window after the migration
+    pub fn migrate_payouts<'info>(ctx: Context<'_, '_, '_, 'info, MigratePayouts<'info>>) -> Result<()> {
+        let config_key = ctx.accounts.config.key();
+        let recipient = ctx.accounts.recipient.key();
+        let authority = ctx.accounts.authority.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+
+        let registry_info = ctx.accounts.payout_registry.to_account_info();
+        require!(
+            registry_info.data_len() == 8 + LEGACY_PAYOUT_REGISTRY_SIZE,
+            VaultError::InvalidMigrationAccount
+        );
+        grow_account(&authority, &registry_info, &system_program, 8 + PayoutRegistry::SIZE)?;
+        let mut registry = PayoutRegistry::try_deserialize(&mut &registry_info.try_borrow_data()?[..])?;
+        require!(registry.recipient == recipient, VaultError::InvalidMigrationAccount);
+        require!(
+            ctx.remaining_accounts.len() == registry.payout_count as usize,
+            VaultError::InvalidMigrationAccount
+        );
+
+        let mut receipts = Vec::with_capacity(ctx.remaining_accounts.len());
+        let mut previous_timestamp = i64::MIN;
+        for receipt_info in ctx.remaining_accounts.iter() {
+            // A receipt passed twice is no longer legacy-sized the second time
+            require!(
+                receipt_info.owner == &crate::ID
+                    && receipt_info.is_writable
+                    && receipt_info.data_len() == 8 + LEGACY_PAYOUT_RECEIPT_SIZE,
+                VaultError::InvalidMigrationAccount
+            );
+            grow_account(&authority, receipt_info, &system_program, 8 + PayoutReceipt::SIZE)?;
+            let receipt = PayoutReceipt::try_deserialize(&mut &receipt_info.try_borrow_data()?[..])?;
+            let (receipt_key, _) = Pubkey::find_program_address(
+                &[b"receipt", config_key.as_ref(), &receipt.race_id_hash, recipient.as_ref()],
+                &crate::ID,
+            );
+            require!(receipt_info.key() == receipt_key, VaultError::InvalidMigrationAccount);
+            require!(receipt.timestamp >= previous_timestamp, VaultError::InvalidMigrationAccount);
+            previous_timestamp = receipt.timestamp;
+            receipts.push(receipt);
+        }
+
+        // Walk back from the newest receipt until the pending total is accounted for
+        let mut pending_count = 0;
+        let mut pending_total: u64 = 0;
+        while pending_total < registry.total_pending {
+            require!(pending_count < receipts.len(), VaultError::InvalidMigrationAccount);
+            pending_count += 1;
+            pending_total = pending_total
+                .checked_add(receipts[receipts.len() - pending_count].amount)
+                .ok_or(VaultError::Overflow)?;
+        }
+        require!(pending_total == registry.total_pending, VaultError::InvalidMigrationAccount);
+        let claimed_count = (receipts.len() - pending_count) as u32;
+
+        // Pending legacy payouts get a full claim window from now rather than
+        // expiring retroactively; settled ones have nothing left to expire
+        let now = Clock::get()?.unix_timestamp;
+        let expires_at = ctx.accounts.config.payout_expiry(now)?;
+        for (sequence, (receipt_info, receipt)) in ctx.remaining_accounts.iter().zip(receipts.iter_mut()).enumerate() {
+            receipt.status = PayoutStatus::Active;
+            receipt.sequence = sequence as u32;
+            receipt.expires_at = if receipt.sequence >= claimed_count { expires_at } else { 0 };
+            receipt.has_race = false;  // Legacy payouts predate races and seasons
+            receipt.season_id = 0;
+            receipt.try_serialize(&mut &mut receipt_info.try_borrow_mut_data()?[..])?;
+        }
+        registry.claimed_count = claimed_count;
+        registry.try_serialize(&mut &mut registry_info.try_borrow_mut_data()?[..])?;
+
+        emit!(PayoutsMigratedEvent {
+            recipient,
+            receipt_count: receipts.len() as u32,
+            claimed_count,
+            pending_total,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Migrate one referrer's bonus records from the layout without claimed_at (admin only)
+    /// remaining_accounts holds every ReferralBonus of the referrer, the legacy ones in
+    /// registration order. Legacy claims paid everything pending at once, so the pending
+    /// legacy bonuses are the newest ones and, with the unclaimed current ones, sum to
+    /// the registry's total_pending; the older legacy bonuses are marked claimed
+    pub fn migrate_referral_bonuses<'info>(
+        ctx: Context<'_, '_, '_, 'info, MigrateReferralBonuses<'info>>,
+    ) -> Result<()> {
+        let config_key = ctx.accounts.config.key();
+        let referrer = ctx.accounts.referrer.key();
+        let authority = ctx.accounts.authority.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let registry = &ctx.accounts.referrer_registry;
+        require!(
+            ctx.remaining_accounts.len() == registry.bonus_count as usize,
+            VaultError::InvalidMigrationAccount
+        );
+
+        // A duplicated bonus would stand in for a missing one
+        let mut keys: Vec<Pubkey> = ctx.remaining_accounts.iter().map(|info| info.key()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        require!(keys.len() == ctx.remaining_accounts.len(), VaultError::InvalidMigrationAccount);
+
+        let mut legacy_bonuses = Vec::new();
+        let mut current_pending: u64 = 0;
+        let mut previous_timestamp = i64::MIN;
+        for bonus_info in ctx.remaining_accounts.iter() {
+            require!(
+                bonus_info.owner == &crate::ID && bonus_info.is_writable,
+                VaultError::InvalidMigrationAccount
+            );
+            let is_legacy = bonus_info.data_len() == 8 + LEGACY_REFERRAL_BONUS_SIZE;
+            if is_legacy {
+                // Race ids over 24 bytes leave no room for claimed_at in the legacy size
+                grow_account(&authority, bonus_info, &system_program, 8 + ReferralBonus::SIZE)?;
+            } else {
+                require!(bonus_info.data_len() == 8 + ReferralBonus::SIZE, VaultError::InvalidMigrationAccount);
+            }
+            let bonus = ReferralBonus::try_deserialize(&mut &bonus_info.try_borrow_data()?[..])?;
+            let (bonus_key, _) = Pubkey::find_program_address(
+                &[
+                    b"referral_bonus",
+                    config_key.as_ref(),
+                    bonus.race_id.as_bytes(),
+                    bonus.referrer.as_ref(),
+                    bonus.referee.as_ref(),
+                ],
+                &crate::ID,
+            );
+            require!(
+                bonus_info.key() == bonus_key && bonus.referrer == referrer,
+                VaultError::InvalidMigrationAccount
+            );
+
+            if is_legacy {
+                require!(bonus.timestamp >= previous_timestamp, VaultError::InvalidMigrationAccount);
+                previous_timestamp = bonus.timestamp;
+                legacy_bonuses.push((bonus_info, bonus));
+            } else if !bonus.claimed {
+                current_pending = current_pending.checked_add(bonus.amount).ok_or(VaultError::Overflow)?;
+            }
+        }
+
+        // Walk back from the newest legacy bonus until its share of the pending total is accounted for
+        let legacy_pending = registry.total_pending
+            .checked_sub(current_pending)
+            .ok_or(VaultError::InvalidMigrationAccount)?;
+        let mut pending_count = 0;
+        let mut pending_total: u64 = 0;
+        while pending_total < legacy_pending {
+            require!(pending_count < legacy_bonuses.len(), VaultError::InvalidMigrationAccount);
+            pending_count += 1;
+            pending_total = pending_total
+                .checked_add(legacy_bonuses[legacy_bonuses.len() - pending_count].1.amount)
+                .ok_or(VaultError::Overflow)?;
+        }
+        require!(pending_total == legacy_pending, VaultError::InvalidMigrationAccount);
+        let claimed_count = legacy_bonuses.len() - pending_count;
+
+        // The legacy claim time was never recorded; the migration stands in for it
+        let now = Clock::get()?.unix_timestamp;
+        for (bonus_info, bonus) in legacy_bonuses.iter_mut().take(claimed_count) {
+            bonus.claimed = true;
+            bonus.claimed_at = now;
+            bonus.try_serialize(&mut &mut bonus_info.try_borrow_mut_data()?[..])?;
+        }
+
+        emit!(ReferralBonusesMigratedEvent {
+            referrer,
+            bonus_count: legacy_bonuses.len() as u32,
+            claimed_count: claimed_count as u32,
+            pending_total,
+            timestamp: now,
+        });
+
         Ok(())
     }
 
+    /// Deposit tokens into the vault (anyone can call)
+    /// Credits the depositor's ledger
+    pub fn deposit<'info>(
+        ctx: Context<'_, '_, '_, 'info, Deposit<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        deposit_tokens(ctx, amount, None)
+    }
+
+    /// Deposit tokens with a purpose tag for sponsors (anyone can call)
+    /// Same as deposit, with `memo` recorded on the DepositEvent
+    pub fn deposit_with_memo<'info>(
+        ctx: Context<'_, '_, '_, 'info, Deposit<'info>>,
+        amount: u64,
+        memo: String,
+    ) -> Result<()> {
+        deposit_tokens(ctx, amount, Some(memo))
+    }
+
+    /// Deposit lamports into the SOL vault (anyone can call)
+    /// Credits the depositor's SOL ledger
+    pub fn deposit_sol(ctx: Context<DepositSol>, amount: u64) -> Result<()> {
+        deposit_lamports(ctx, amount, None)
+    }
+
+    /// Deposit lamports with a purpose tag for sponsors (anyone can call)
+    /// Same as deposit_sol, with `memo` recorded on the DepositEvent
+    pub fn deposit_sol_with_memo(ctx: Context<DepositSol>, amount: u64, memo: String) -> Result<()> {
+        deposit_lamports(ctx, amount, Some(memo))
+    }
+
     /// Reconcile: reads actual vault balance and emits event for off-chain tracking.
     /// Use this to sync tokens sent directly to vault (not through deposit).
     pub fn reconcile(ctx: Context<Reconcile>) -> Result<()> {
@@ -56,21 +374,129 @@ pub mod race_vault {
         Ok(())
     }
 
-    /// Register a payout (admin-only) - creates a pending payout receipt
-    /// Client must provide hash(race_id) - validated on-chain for security
-    pub fn register_payout(
-        ctx: Context<RegisterPayout>,
-        race_id: String,
-        race_id_hash: [u8; 32],  // Client provides hash, we validate
-        points: u64,
+    /// Withdraw unreserved vault tokens (treasurer only)
+    /// Only the balance above all pending payouts, referral bonuses and entry fees
+    /// held for unsettled races can leave, so every obligation stays fully funded
+    pub fn withdraw_surplus<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawSurplus<'info>>,
         amount: u64,
     ) -> Result<()> {
         let config = &ctx.accounts.config;
-        
+
         // Check if paused
         require!(!config.paused, VaultError::ProgramPaused);
         require!(amount > 0, VaultError::ZeroAmount);
-        
+
+        let entry_fees = entry_fees_held(&ctx.accounts.global_entry_fee_registry)?;
+        let reserved = ctx.accounts.global_payout_registry.total_pending
+            .checked_add(referral_pending_in(
+                config,
+                &ctx.accounts.mint.key(),
+                &ctx.accounts.global_referral_registry,
+            ))
+            .and_then(|total| total.checked_add(entry_fees))
+            .ok_or(VaultError::Overflow)?;
+        let surplus = ctx.accounts.vault_token.amount.saturating_sub(reserved);
+        require!(amount <= surplus, VaultError::InsufficientSurplus);
+
+        // PDA signer seeds
+        let config_key = config.key();
+        let seeds: &[&[u8]] = &[
+            b"vault_signer",
+            config_key.as_ref(),
+            &[config.vault_signer_bump],
+        ];
+        let signer = &[seeds];
+
+        transfer_checked_with_hook(
+            ctx.accounts.vault_token.to_account_info(),
+            &ctx.accounts.mint,
+            ctx.accounts.destination_token.to_account_info(),
+            ctx.accounts.vault_signer.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            signer,
+        )?;
+
+        emit!(SurplusWithdrawnEvent {
+            treasurer: ctx.accounts.treasurer.key(),
+            destination: ctx.accounts.destination_token.key(),
+            amount,
+            reserved,
+            remaining_balance: ctx.accounts.vault_token.amount - amount,
+            mint: ctx.accounts.mint.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create an immutable prize distribution table (admin only)
+    /// PercentOfPool pays `place_shares_bps[i]` of the race's prize budget to place i;
+    /// PointsRate pays `points * points_rate` base units to every finisher
+    pub fn create_prize_table(
+        ctx: Context<CreatePrizeTable>,
+        table_id: u64,
+        mode: PrizeMode,
+        place_shares_bps: Vec<u16>,
+        points_rate: u64,
+    ) -> Result<()> {
+        match mode {
+            PrizeMode::PercentOfPool => {
+                require!(
+                    !place_shares_bps.is_empty() && place_shares_bps.len() <= MAX_PRIZE_PLACES,
+                    VaultError::InvalidPrizeTable
+                );
+                let total_bps = place_shares_bps.iter().map(|share| *share as u32).sum::<u32>();
+                require!(total_bps <= BPS_DENOMINATOR as u32, VaultError::InvalidPrizeTable);
+                require!(points_rate == 0, VaultError::InvalidPrizeTable);
+            }
+            PrizeMode::PointsRate => {
+                require!(place_shares_bps.is_empty(), VaultError::InvalidPrizeTable);
+                require!(points_rate > 0, VaultError::InvalidPrizeTable);
+            }
+        }
+
+        let table = &mut ctx.accounts.prize_table;
+        table.table_id = table_id;
+        table.mode = mode;
+        table.points_rate = points_rate;
+        table.place_shares_bps = place_shares_bps;
+
+        emit!(PrizeTableCreatedEvent {
+            table_id,
+            mode,
+            place_shares_bps: table.place_shares_bps.clone(),
+            points_rate,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create a race with a prize budget in one accepted mint (operator only)
+    /// `mint` may be SOL_DENOMINATION for a race paid in lamports; entry fees
+    /// (token races only) are added to the prize budget as racers enter. Passing a
+    /// prize table lets settle_race compute the payouts on-chain
+    pub fn create_race(
+        ctx: Context<CreateRace>,
+        race_id: String,
+        race_id_hash: [u8; 32],  // Client provides hash, we validate
+        prize_budget: u64,
+        entry_fee: u64,
+    ) -> Result<()> {
+        require!(prize_budget > 0 || entry_fee > 0, VaultError::ZeroAmount);
+        // settle_race pays tables in tokens only
+        require!(
+            ctx.accounts.prize_table.is_none() || ctx.accounts.mint.key() != SOL_DENOMINATION,
+            VaultError::InvalidPrizeTable
+        );
+        // enter_race collects fees through a token account, which a lamport race has none of
+        require!(
+            entry_fee == 0 || ctx.accounts.mint.key() != SOL_DENOMINATION,
+            VaultError::EntryFeeRequiresToken
+        );
+
         // Validate the hash matches the race_id
         let computed_hash = hash(race_id.as_bytes()).to_bytes();
         require!(
@@ -78,511 +504,5139 @@ pub mod race_vault {
             VaultError::InvalidRaceIdHash
         );
 
-        // Write receipt (prevents double-registration)
-        let receipt = &mut ctx.accounts.payout_receipt;
-        receipt.race_id_hash = race_id_hash;
-        receipt.recipient = ctx.accounts.recipient.key();
-        receipt.points = points;
-        receipt.amount = amount;
-        receipt.timestamp = Clock::get()?.unix_timestamp;
+        let race = &mut ctx.accounts.race;
+        race.race_id_hash = race_id_hash;
+        race.mint = ctx.accounts.mint.key();
+        race.status = RaceStatus::Open;
+        race.prize_budget = prize_budget;
+        race.settled_total = 0;
+        race.finisher_count = 0;
+        race.created_at = Clock::get()?.unix_timestamp;
+        race.started_at = 0;
+        race.ended_at = 0;
+        race.entry_fee = entry_fee;
+        race.entrant_count = 0;
+        race.entry_fees_collected = 0;
+        race.prize_table = ctx.accounts.prize_table.as_ref().map_or(Pubkey::default(), |table| table.key());
+
+        emit!(RaceCreatedEvent {
+            race_id,
+            race_id_hash,
+            prize_budget,
+            entry_fee,
+            prize_table: race.prize_table,
+            mint: race.mint,
+            timestamp: race.created_at,
+        });
 
-        // Update payout registry
-        let registry = &mut ctx.accounts.payout_registry;
-        
-        // Track if this is a new recipient for global stats
-        let is_new_recipient = registry.recipient == Pubkey::default();
-        
-        // Initialize registry if this is the first payout
-        if is_new_recipient {
-            registry.recipient = ctx.accounts.recipient.key();
-        }
-        
-        registry.total_pending = registry.total_pending.checked_add(amount).ok_or(VaultError::Overflow)?;
-        registry.payout_count += 1;
-        registry.last_updated = Clock::get()?.unix_timestamp;
-        
-        // Update global payout registry
-        let global_registry = &mut ctx.accounts.global_payout_registry;
-        global_registry.total_pending = global_registry.total_pending.checked_add(amount).ok_or(VaultError::Overflow)?;
-        global_registry.total_payout_count += 1;
-        if is_new_recipient {
-            global_registry.total_recipient_count += 1;
+        Ok(())
+    }
+
+    /// Enter an open race by paying its entry fee into the vault (racer signs)
+    /// The fee actually received is added to the race's prize budget and recorded
+    /// on the racer's EntryTicket; remaining accounts go to the transfer hook
+    pub fn enter_race<'info>(
+        ctx: Context<'_, '_, '_, 'info, EnterRace<'info>>,
+        race_id_hash: [u8; 32],
+    ) -> Result<()> {
+        // Check if paused
+        require!(!ctx.accounts.config.paused, VaultError::ProgramPaused);
+
+        let entry_fee = ctx.accounts.race.entry_fee;
+        let balance_before = ctx.accounts.vault_token.amount;
+        if entry_fee > 0 {
+            transfer_checked_with_hook(
+                ctx.accounts.racer_token.to_account_info(),
+                &ctx.accounts.mint,
+                ctx.accounts.vault_token.to_account_info(),
+                ctx.accounts.racer.to_account_info(),
+                ctx.remaining_accounts,
+                entry_fee,
+                &[],
+            )?;
         }
-        global_registry.last_updated = Clock::get()?.unix_timestamp;
 
-        // Emit for off-chain indexing
-        emit!(PayoutRegisteredEvent {
-            race_id,  // Original CUID for off-chain indexing
-            race_id_hash,  // Hash for on-chain lookups
-            recipient: receipt.recipient,
-            points,
-            amount,
-            timestamp: receipt.timestamp,
+        // Credit what actually arrived, net of any Token-2022 transfer fee
+        ctx.accounts.vault_token.reload()?;
+        let received = ctx.accounts.vault_token.amount
+            .checked_sub(balance_before)
+            .ok_or(VaultError::Overflow)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let ticket = &mut ctx.accounts.entry_ticket;
+        ticket.race_id_hash = race_id_hash;
+        ticket.racer = ctx.accounts.racer.key();
+        ticket.amount = received;
+        ticket.entered_at = now;
+
+        let race = &mut ctx.accounts.race;
+        race.prize_budget = race.prize_budget.checked_add(received).ok_or(VaultError::Overflow)?;
+        race.entry_fees_collected = race.entry_fees_collected.checked_add(received).ok_or(VaultError::Overflow)?;
+        race.entrant_count = race.entrant_count.checked_add(1).ok_or(VaultError::Overflow)?;
+
+        // The fee stays reserved until the race settles or the entry is refunded
+        let fee_registry = &mut ctx.accounts.global_entry_fee_registry;
+        fee_registry.total_held = fee_registry.total_held.checked_add(received).ok_or(VaultError::Overflow)?;
+        fee_registry.total_collected = fee_registry.total_collected.checked_add(received).ok_or(VaultError::Overflow)?;
+        fee_registry.last_updated = now;
+
+        emit!(RaceEnteredEvent {
+            race_id_hash,
+            racer: ticket.racer,
+            amount: received,
+            transfer_fee: entry_fee.saturating_sub(received),
+            prize_budget: race.prize_budget,
+            entrant_count: race.entrant_count,
+            mint: race.mint,
+            timestamp: now,
         });
 
         Ok(())
     }
 
-    /// Claim all pending payouts for a wallet (anyone can call)
-    /// Transfers all pending payouts to the recipient's token account
-    pub fn claim_pending_payouts(
-        ctx: Context<ClaimPendingPayouts>,
-        recipient: Pubkey,
+    /// Refund an entry fee from a cancelled race (anyone can call)
+    /// Pays the ticket amount back to the racer and closes the ticket, returning its rent
+    pub fn refund_entry<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefundEntry<'info>>,
+        race_id_hash: [u8; 32],
     ) -> Result<()> {
         let config = &ctx.accounts.config;
-        
+
         // Check if paused
         require!(!config.paused, VaultError::ProgramPaused);
-        
-        // Validate recipient matches the account
-        require!(
-            recipient == ctx.accounts.recipient.key(),
-            VaultError::InvalidRecipient
-        );
 
-        // Get pending payouts from registry
-        let registry = &mut ctx.accounts.payout_registry;
-        
-        // Check if there are pending payouts
-        require!(registry.total_pending > 0, VaultError::NoPendingPayouts);
-        
-        // Check vault has sufficient balance
-        require!(
-            ctx.accounts.vault_token.amount >= registry.total_pending,
-            VaultError::InsufficientBalance
-        );
+        let amount = ctx.accounts.entry_ticket.amount;
+        let race = &mut ctx.accounts.race;
+        race.prize_budget = race.prize_budget.checked_sub(amount).ok_or(VaultError::Overflow)?;
+        race.entry_fees_collected = race.entry_fees_collected.checked_sub(amount).ok_or(VaultError::Overflow)?;
+        race.entrant_count = race.entrant_count.checked_sub(1).ok_or(VaultError::Overflow)?;
+
+        // Refunds are paid out of the fees reserved for the cancelled race
+        let fee_registry = &mut ctx.accounts.global_entry_fee_registry;
+        fee_registry.total_held = fee_registry.total_held.checked_sub(amount).ok_or(VaultError::Overflow)?;
+        fee_registry.last_updated = Clock::get()?.unix_timestamp;
+
+        if amount > 0 {
+            // Check vault has sufficient balance
+            require!(
+                ctx.accounts.vault_token.amount >= amount,
+                VaultError::InsufficientBalance
+            );
+
+            // PDA signer seeds
+            let config_key = config.key();
+            let seeds: &[&[u8]] = &[
+                b"vault_signer",
+                config_key.as_ref(),
+                &[config.vault_signer_bump],
+            ];
+            let signer = &[seeds];
+
+            transfer_checked_with_hook(
+                ctx.accounts.vault_token.to_account_info(),
+                &ctx.accounts.mint,
+                ctx.accounts.racer_token.to_account_info(),
+                ctx.accounts.vault_signer.to_account_info(),
+                ctx.remaining_accounts,
+                amount,
+                signer,
+            )?;
+        }
 
-        let total_pending = registry.total_pending;
-        let payout_count = registry.payout_count;
+        // The ticket is closed on exit via its `close` constraint
+        emit!(EntryRefundedEvent {
+            race_id_hash,
+            racer: ctx.accounts.racer.key(),
+            amount,
+            transfer_fee: transfer_fee(&ctx.accounts.mint, amount)?,
+            mint: ctx.accounts.mint.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-        // Update registry - move pending to claimed
-        registry.total_pending = 0;
-        registry.total_claimed = registry.total_claimed.checked_add(total_pending).ok_or(VaultError::Overflow)?;
-        registry.last_updated = Clock::get()?.unix_timestamp;
-        
-        // Update global registry - move pending to claimed
-        let global_registry = &mut ctx.accounts.global_payout_registry;
-        global_registry.total_pending = global_registry.total_pending.checked_sub(total_pending).ok_or(VaultError::Overflow)?;
-        global_registry.total_claimed = global_registry.total_claimed.checked_add(total_pending).ok_or(VaultError::Overflow)?;
-        global_registry.last_updated = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
 
-        // Transfer from vault (PDA signer) to recipient ATA
-        let cfg = &ctx.accounts.config;
+    /// Move an open race to Running (operator only)
+    /// Payouts can be registered against a race while it is running
+    pub fn start_race(ctx: Context<UpdateRace>, _race_id_hash: [u8; 32]) -> Result<()> {
+        let race = &mut ctx.accounts.race;
+        require!(race.status == RaceStatus::Open, VaultError::InvalidRaceTransition);
+        race.status = RaceStatus::Running;
+        race.started_at = Clock::get()?.unix_timestamp;
 
-        // PDA signer seeds
-        let config_key = cfg.key();
-        let seeds: &[&[u8]] = &[
-            b"vault_signer",
-            config_key.as_ref(),
-            &[cfg.vault_signer_bump],
-        ];
-        
-        let signer = &[seeds];
+        emit_race_status(race, race.started_at);
+        Ok(())
+    }
 
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.vault_token.to_account_info(),
-            to: ctx.accounts.recipient_token.to_account_info(),
-            authority: ctx.accounts.vault_signer.to_account_info(),
-        };
-        let cpi_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            cpi_accounts,
-            signer,
-        );
-        token::transfer(cpi_ctx, total_pending)?;
+    /// Close settlement of a running race (operator only) - no further payouts
+    /// Entry fees not used by its payouts are released to the surplus
+    pub fn complete_race(ctx: Context<UpdateRace>, _race_id_hash: [u8; 32]) -> Result<()> {
+        let race = &mut ctx.accounts.race;
+        require!(race.status == RaceStatus::Running, VaultError::InvalidRaceTransition);
+        let fees_held = race.entry_fees_held();
+        race.status = RaceStatus::Settled;
+        race.ended_at = Clock::get()?.unix_timestamp;
 
-        // Emit for off-chain indexing
-        emit!(PayoutsClaimedEvent {
-            recipient: registry.recipient,
-            total_amount: total_pending,
-            payout_count,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+        // Fees the payouts did not use become surplus
+        sync_entry_fees(&ctx.accounts.global_entry_fee_registry, fees_held, 0, race.ended_at)?;
 
+        emit_race_status(race, race.ended_at);
         Ok(())
     }
 
-    /// Transfer authority to new admin (current authority only)
-    pub fn transfer_authority(
-        ctx: Context<TransferAuthority>,
-        new_authority: Pubkey,
-    ) -> Result<()> {
-        let config = &mut ctx.accounts.config;
-        let old_authority = config.authority;
-        config.authority = new_authority;
-
-        emit!(AuthorityTransferEvent {
-            old_authority,
-            new_authority,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+    /// Cancel a race that has no payouts registered (operator only)
+    pub fn cancel_race(ctx: Context<UpdateRace>, _race_id_hash: [u8; 32]) -> Result<()> {
+        let race = &mut ctx.accounts.race;
+        require!(
+            matches!(race.status, RaceStatus::Open | RaceStatus::Running),
+            VaultError::InvalidRaceTransition
+        );
+        require!(race.finisher_count == 0, VaultError::RaceHasPayouts);
+        race.status = RaceStatus::Cancelled;
+        race.ended_at = Clock::get()?.unix_timestamp;
 
+        emit_race_status(race, race.ended_at);
         Ok(())
     }
 
-    /// Update config parameters (admin only)
-    pub fn update_config(
-        ctx: Context<UpdateConfig>,
-        paused: Option<bool>,
+    /// Open a season (admin only) - registrations between `start_at` and `end_at`
+    /// count towards it, and those in its mint against its budget.
+    /// Only one season is active at a time; it becomes `config.season_id`.
+    /// Also creates the season's empty Leaderboard
+    pub fn open_season(
+        ctx: Context<OpenSeason>,
+        season_id: u64,
+        start_at: i64,
+        end_at: i64,
+        budget: u64,
     ) -> Result<()> {
-        let config = &mut ctx.accounts.config;
+        require!(season_id != 0, VaultError::InvalidSeason);
+        require!(end_at > start_at, VaultError::InvalidSeason);
+        require!(budget > 0, VaultError::ZeroAmount);
 
-        if let Some(pause_state) = paused {
-            config.paused = pause_state;
-        }
-
-        emit!(ConfigUpdateEvent {
-            paused: config.paused,
+        let config = &mut ctx.accounts.config;
+        require!(config.season_id == 0, VaultError::SeasonAlreadyActive);
+        config.season_id = season_id;
+
+        let season = &mut ctx.accounts.season;
+        season.season_id = season_id;
+        season.mint = ctx.accounts.mint.key();
+        season.status = SeasonStatus::Open;
+        season.start_at = start_at;
+        season.end_at = end_at;
+        season.budget = budget;
+        season.total_registered = 0;
+        season.total_claimed = 0;
+        season.payout_count = 0;
+        season.closed_at = 0;
+
+        let mut leaderboard = ctx.accounts.leaderboard.load_init()?;
+        leaderboard.season_id = season_id;
+
+        emit!(SeasonOpenedEvent {
+            season_id,
+            mint: season.mint,
+            start_at,
+            end_at,
+            budget,
             timestamp: Clock::get()?.unix_timestamp,
         });
 
         Ok(())
     }
 
-    /// Close config account (admin only) - for reinitialization or cleanup
-    /// NOTE: This only closes the config account, not the vault token account
-    /// Tokens remain safe in the vault token account
-    pub fn close(ctx: Context<Close>) -> Result<()> {
-        emit!(ConfigCloseEvent {
-            authority: ctx.accounts.authority.key(),
-            timestamp: Clock::get()?.unix_timestamp,
+    /// Close the active season (admin only) - freezes its totals and emits them
+    /// for season-end rewards; later registrations count towards no season
+    pub fn close_season(ctx: Context<CloseSeason>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let season = &mut ctx.accounts.season;
+        require!(season.status == SeasonStatus::Open, VaultError::SeasonNotActive);
+        season.status = SeasonStatus::Closed;
+        season.closed_at = now;
+        ctx.accounts.config.season_id = 0;
+
+        emit!(SeasonClosedEvent {
+            season_id: season.season_id,
+            mint: season.mint,
+            start_at: season.start_at,
+            end_at: season.end_at,
+            budget: season.budget,
+            total_registered: season.total_registered,
+            total_claimed: season.total_claimed,
+            payout_count: season.payout_count,
+            timestamp: now,
         });
+
         Ok(())
     }
 
-    /// Register referral bonus (admin only)
-    /// Creates a referral bonus record for a specific race, referrer, and referee
-    pub fn register_referral_bonus(
-        ctx: Context<RegisterReferralBonus>,
+    /// Register a payout (operator only) - creates a pending payout receipt
+    /// Client must provide hash(race_id) - validated on-chain for security
+    /// The race must be Running without a prize table, and the payout counts against
+    /// its prize budget. With `amounts_from_points` on, the amount is derived from `points`
+    pub fn register_payout(
+        ctx: Context<RegisterPayout>,
         race_id: String,
-        referrer: Pubkey,
-        referee: Pubkey,
+        race_id_hash: [u8; 32],  // Client provides hash, we validate
+        points: u64,
         amount: u64,
     ) -> Result<()> {
         let config = &ctx.accounts.config;
         
         // Check if paused
         require!(!config.paused, VaultError::ProgramPaused);
+        let amount = payout_amount(config, points, amount, ctx.accounts.mint.decimals)?;
         require!(amount > 0, VaultError::ZeroAmount);
         
-        // Validate race_id length (Solana PDA seeds max 32 bytes)
-        require!(race_id.len() <= 32, VaultError::RaceIdTooLong);
-        
-        // Prevent self-referrals
-        require!(referrer != referee, VaultError::SelfReferralNotAllowed);
-
-        // Create referral bonus record
-        let referral_bonus = &mut ctx.accounts.referral_bonus;
-        referral_bonus.race_id = race_id;
-        referral_bonus.referrer = referrer;
-        referral_bonus.referee = referee;
-        referral_bonus.amount = amount;
-        referral_bonus.claimed = false;
-        referral_bonus.timestamp = Clock::get()?.unix_timestamp;
+        // Validate the hash matches the race_id
+        let computed_hash = hash(race_id.as_bytes()).to_bytes();
+        require!(
+            computed_hash == race_id_hash,
+            VaultError::InvalidRaceIdHash
+        );
 
-        // Update referrer registry
-        let registry = &mut ctx.accounts.referrer_registry;
-        
-        // Initialize registry if this is the first bonus (H-01 fix)
-        if registry.referrer == Pubkey::default() {
-            registry.referrer = referrer;
+        // Write receipt (prevents double-registration)
+        let receipt = &mut ctx.accounts.payout_receipt;
+        receipt.race_id_hash = race_id_hash;
+        receipt.recipient = ctx.accounts.recipient.key();
+        receipt.points = points;
+        receipt.amount = amount;
+        receipt.timestamp = Clock::get()?.unix_timestamp;
+        receipt.status = PayoutStatus::Active;
+        receipt.expires_at = config.payout_expiry(receipt.timestamp)?;
+        receipt.has_race = true;
+        receipt.season_id = registration_season(config, &ctx.accounts.season, receipt.timestamp)?;
+
+        // Update payout registry and global payout registry
+        receipt.sequence = credit_payout(
+            &mut ctx.accounts.payout_registry,
+            &mut ctx.accounts.global_payout_registry,
+            receipt.recipient,
+            amount,
+            receipt.season_id,
+            receipt.timestamp,
+        )?;
+        let fees_held = ctx.accounts.race.entry_fees_held();
+        ctx.accounts.race.record_payouts(amount, 1)?;
+        let entry_fees = sync_entry_fees(
+            &ctx.accounts.global_entry_fee_registry,
+            fees_held,
+            ctx.accounts.race.entry_fees_held(),
+            receipt.timestamp,
+        )?;
+        attribute_registration(config, &mut ctx.accounts.season, &ctx.accounts.mint.key(), amount, 1, receipt.timestamp)?;
+
+        // Registrations carry no finishing place; season points only count within the season
+        ctx.accounts.racer_stats.record_race(receipt.recipient, points, 0, receipt.timestamp)?;
+        if receipt.season_id != 0 {
+            let season_points = record_stats(
+                config.key(),
+                &ctx.accounts.season_stats,
+                receipt.recipient,
+                receipt.season_id,
+                points,
+                0,
+                &ctx.accounts.operator.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                receipt.timestamp,
+            )?;
+            update_leaderboard(receipt.season_id, &ctx.accounts.leaderboard, receipt.recipient, season_points)?;
+        }
+
+        // Reject registrations the vault balance cannot back
+        require_solvent(
+            config,
+            ctx.accounts.vault_token.amount,
+            ctx.accounts.global_payout_registry.total_pending,
+            referral_pending_in(config, &ctx.accounts.mint.key(), &ctx.accounts.global_referral_registry),
+            entry_fees,
+        )?;
+
+        // Emit for off-chain indexing
+        emit!(PayoutRegisteredEvent {
+            race_id,  // Original CUID for off-chain indexing
+            race_id_hash,  // Hash for on-chain lookups
+            recipient: receipt.recipient,
+            points,
+            amount,
+            mint: ctx.accounts.mint.key(),
+            timestamp: receipt.timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Register a lamport payout (operator only) - creates a pending payout receipt
+    /// Same receipt and registries as register_payout, keyed by SOL_DENOMINATION;
+    /// the race must be Running, denominated in SOL and without a prize table
+    pub fn register_sol_payout(
+        ctx: Context<RegisterSolPayout>,
+        race_id: String,
+        race_id_hash: [u8; 32],  // Client provides hash, we validate
+        points: u64,
+        amount: u64,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+
+        // Check if paused
+        require!(!config.paused, VaultError::ProgramPaused);
+        let amount = payout_amount(config, points, amount, SOL_DECIMALS)?;
+        require!(amount > 0, VaultError::ZeroAmount);
+
+        // Validate the hash matches the race_id
+        let computed_hash = hash(race_id.as_bytes()).to_bytes();
+        require!(
+            computed_hash == race_id_hash,
+            VaultError::InvalidRaceIdHash
+        );
+
+        // Write receipt (prevents double-registration)
+        let receipt = &mut ctx.accounts.payout_receipt;
+        receipt.race_id_hash = race_id_hash;
+        receipt.recipient = ctx.accounts.recipient.key();
+        receipt.points = points;
+        receipt.amount = amount;
+        receipt.timestamp = Clock::get()?.unix_timestamp;
+        receipt.status = PayoutStatus::Active;
+        receipt.expires_at = config.payout_expiry(receipt.timestamp)?;
+        receipt.has_race = true;
+        receipt.season_id = registration_season(config, &ctx.accounts.season, receipt.timestamp)?;
+
+        // Update payout registry and global payout registry
+        receipt.sequence = credit_payout(
+            &mut ctx.accounts.payout_registry,
+            &mut ctx.accounts.global_payout_registry,
+            receipt.recipient,
+            amount,
+            receipt.season_id,
+            receipt.timestamp,
+        )?;
+        ctx.accounts.race.record_payouts(amount, 1)?;
+        attribute_registration(config, &mut ctx.accounts.season, &SOL_DENOMINATION, amount, 1, receipt.timestamp)?;
+
+        // Registrations carry no finishing place; season points only count within the season
+        ctx.accounts.racer_stats.record_race(receipt.recipient, points, 0, receipt.timestamp)?;
+        if receipt.season_id != 0 {
+            let season_points = record_stats(
+                config.key(),
+                &ctx.accounts.season_stats,
+                receipt.recipient,
+                receipt.season_id,
+                points,
+                0,
+                &ctx.accounts.operator.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                receipt.timestamp,
+            )?;
+            update_leaderboard(receipt.season_id, &ctx.accounts.leaderboard, receipt.recipient, season_points)?;
         }
+
+        // Reject registrations the SOL vault cannot back; referral bonuses and entry fees are never in SOL
+        require_solvent(
+            config,
+            sol_vault_balance(&ctx.accounts.sol_vault)?,
+            ctx.accounts.global_payout_registry.total_pending,
+            0,
+            0,
+        )?;
+
+        // Emit for off-chain indexing
+        emit!(PayoutRegisteredEvent {
+            race_id,  // Original CUID for off-chain indexing
+            race_id_hash,  // Hash for on-chain lookups
+            recipient: receipt.recipient,
+            points,
+            amount,
+            mint: SOL_DENOMINATION,
+            timestamp: receipt.timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Register payouts for every finisher of a race in one instruction (operator only)
+    /// remaining_accounts holds one (payout_receipt, payout_registry, racer_stats,
+    /// season_stats) group per entry, in entry order; receipts are created here and
+    /// registries and stats on first use.
+    /// The race must be Running without a prize table, and the batch total counts
+    /// against its prize budget; entry amounts are derived from points when
+    /// `amounts_from_points` is on. Up to MAX_BATCH_PAYOUTS entries fit one transaction
+    /// when the accounts are in an address lookup table; without one, only 3 fit
+    pub fn register_race_payouts<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RegisterRacePayouts<'info>>,
+        race_id: String,
+        race_id_hash: [u8; 32],  // Client provides hash, we validate
+        entries: Vec<PayoutEntry>,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let mut entries = entries;  // Amounts may be derived from points below
         
-        registry.total_pending = registry.total_pending.checked_add(amount).ok_or(VaultError::Overflow)?;
-        registry.bonus_count += 1;
-        registry.last_updated = Clock::get()?.unix_timestamp;
+        // Check if paused
+        require!(!config.paused, VaultError::ProgramPaused);
+        require!(!entries.is_empty(), VaultError::EmptyPayoutBatch);
+        require!(entries.len() <= MAX_BATCH_PAYOUTS, VaultError::PayoutBatchTooLarge);
+        require!(
+            ctx.remaining_accounts.len() == entries.len() * BATCH_ENTRY_ACCOUNTS,
+            VaultError::InvalidRemainingAccounts
+        );
+        
+        // Validate the hash matches the race_id (once for the whole race)
+        let computed_hash = hash(race_id.as_bytes()).to_bytes();
+        require!(
+            computed_hash == race_id_hash,
+            VaultError::InvalidRaceIdHash
+        );
 
-        // Emit event for off-chain tracking
-        emit!(ReferralBonusRegisteredEvent {
-            race_id: referral_bonus.race_id.clone(),
-            referrer: referral_bonus.referrer,
-            referee: referral_bonus.referee,
-            amount: referral_bonus.amount,
-            timestamp: referral_bonus.timestamp,
+        let config_key = config.key();
+        let mint_key = ctx.accounts.mint.key();
+        let mint_seed = mint_seed(config, &mint_key);
+        let now = Clock::get()?.unix_timestamp;
+        let payer = ctx.accounts.operator.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let season_id = registration_season(config, &ctx.accounts.season, now)?;
+        let mut total_points: u64 = 0;
+        let mut total_amount: u64 = 0;
+        let mut new_recipients: u32 = 0;
+
+        let decimals = ctx.accounts.mint.decimals;
+        for (entry, accounts) in entries.iter_mut().zip(ctx.remaining_accounts.chunks(BATCH_ENTRY_ACCOUNTS)) {
+            entry.amount = payout_amount(config, entry.points, entry.amount, decimals)?;
+            require!(entry.amount > 0, VaultError::ZeroAmount);
+            let (season_points, is_new_recipient) = register_batch_entry(
+                config,
+                config_key,
+                &mint_seed,
+                race_id_hash,
+                entry,
+                0,  // Entries carry no finishing place
+                accounts,
+                &payer,
+                &system_program,
+                season_id,
+                now,
+            )?;
+            update_leaderboard(season_id, &ctx.accounts.leaderboard, entry.recipient, season_points)?;
+
+            total_points = total_points.checked_add(entry.points).ok_or(VaultError::Overflow)?;
+            total_amount = total_amount.checked_add(entry.amount).ok_or(VaultError::Overflow)?;
+            new_recipients += is_new_recipient as u32;
+
+            emit!(PayoutRegisteredEvent {
+                race_id: race_id.clone(),
+                race_id_hash,
+                recipient: entry.recipient,
+                points: entry.points,
+                amount: entry.amount,
+                mint: ctx.accounts.mint.key(),
+                timestamp: now,
+            });
+        }
+
+        ctx.accounts.global_payout_registry.record_payouts(total_amount, entries.len() as u32, new_recipients, now)?;
+        let fees_held = ctx.accounts.race.entry_fees_held();
+        ctx.accounts.race.record_payouts(total_amount, entries.len() as u32)?;
+        let entry_fees = sync_entry_fees(
+            &ctx.accounts.global_entry_fee_registry,
+            fees_held,
+            ctx.accounts.race.entry_fees_held(),
+            now,
+        )?;
+        attribute_registration(config, &mut ctx.accounts.season, &ctx.accounts.mint.key(), total_amount, entries.len() as u32, now)?;
+
+        // Reject registrations the vault balance cannot back
+        require_solvent(
+            config,
+            ctx.accounts.vault_token.amount,
+            ctx.accounts.global_payout_registry.total_pending,
+            referral_pending_in(config, &ctx.accounts.mint.key(), &ctx.accounts.global_referral_registry),
+            entry_fees,
+        )?;
+
+        // One summary event for the whole race
+        emit!(RacePayoutsRegisteredEvent {
+            race_id,
+            race_id_hash,
+            recipient_count: entries.len() as u32,
+            total_points,
+            total_amount,
+            mint: ctx.accounts.mint.key(),
+            timestamp: now,
         });
 
         Ok(())
     }
 
+    /// Settle a running race from its finishing order (operator only)
+    /// Each finisher's amount is computed from the race's prize table, never supplied
+    /// by the client. remaining_accounts holds one (payout_receipt, payout_registry,
+    /// racer_stats, season_stats) group per finisher that earns a non-zero amount, in
+    /// finishing order, and their stats record the place. The race moves to Settled,
+    /// so nothing more can be registered against it
+    pub fn settle_race<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleRace<'info>>,
+        race_id: String,
+        race_id_hash: [u8; 32],  // Client provides hash, we validate
+        finishers: Vec<Finisher>,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+
+        // Check if paused
+        require!(!config.paused, VaultError::ProgramPaused);
+        require!(!finishers.is_empty(), VaultError::EmptyPayoutBatch);
+
+        // Validate the hash matches the race_id (once for the whole race)
+        let computed_hash = hash(race_id.as_bytes()).to_bytes();
+        require!(
+            computed_hash == race_id_hash,
+            VaultError::InvalidRaceIdHash
+        );
+
+        // Amounts come from the table; places it does not pay are skipped
+        let prize_pool = ctx.accounts.race.prize_budget;
+        let mut entries = Vec::with_capacity(finishers.len());
+        for (place, finisher) in finishers.iter().enumerate() {
+            let amount = ctx.accounts.prize_table.payout_for(place, finisher.points, prize_pool)?;
+            if amount > 0 {
+                let entry = PayoutEntry {
+                    recipient: finisher.recipient,
+                    points: finisher.points,
+                    amount,
+                };
+                entries.push((entry, place as u32 + 1));
+            }
+        }
+        require!(!entries.is_empty(), VaultError::EmptyPayoutBatch);
+        require!(entries.len() <= MAX_BATCH_PAYOUTS, VaultError::PayoutBatchTooLarge);
+        require!(
+            ctx.remaining_accounts.len() == entries.len() * BATCH_ENTRY_ACCOUNTS,
+            VaultError::InvalidRemainingAccounts
+        );
+
+        let config_key = config.key();
+        let mint_key = ctx.accounts.mint.key();
+        let mint_seed = mint_seed(config, &mint_key);
+        let now = Clock::get()?.unix_timestamp;
+        let payer = ctx.accounts.operator.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let season_id = registration_season(config, &ctx.accounts.season, now)?;
+        let mut total_points: u64 = 0;
+        let mut total_amount: u64 = 0;
+        let mut new_recipients: u32 = 0;
+
+        for ((entry, place), accounts) in entries.iter().zip(ctx.remaining_accounts.chunks(BATCH_ENTRY_ACCOUNTS)) {
+            let (season_points, is_new_recipient) = register_batch_entry(
+                config,
+                config_key,
+                &mint_seed,
+                race_id_hash,
+                entry,
+                *place,
+                accounts,
+                &payer,
+                &system_program,
+                season_id,
+                now,
+            )?;
+            update_leaderboard(season_id, &ctx.accounts.leaderboard, entry.recipient, season_points)?;
+
+            total_points = total_points.checked_add(entry.points).ok_or(VaultError::Overflow)?;
+            total_amount = total_amount.checked_add(entry.amount).ok_or(VaultError::Overflow)?;
+            new_recipients += is_new_recipient as u32;
+
+            emit!(PayoutRegisteredEvent {
+                race_id: race_id.clone(),
+                race_id_hash,
+                recipient: entry.recipient,
+                points: entry.points,
+                amount: entry.amount,
+                mint: mint_key,
+                timestamp: now,
+            });
+        }
+
+        ctx.accounts.global_payout_registry.record_payouts(total_amount, entries.len() as u32, new_recipients, now)?;
+
+        // The table caps what the race pays; the budget still bounds points-rate tables
+        let race = &mut ctx.accounts.race;
+        let fees_held = race.entry_fees_held();
+        race.record_payouts(total_amount, entries.len() as u32)?;
+        race.status = RaceStatus::Settled;
+        race.ended_at = now;
+
+        // Fees the payouts did not use become surplus
+        let entry_fees = sync_entry_fees(&ctx.accounts.global_entry_fee_registry, fees_held, 0, now)?;
+
+        // Reject registrations the vault balance cannot back
+        require_solvent(
+            config,
+            ctx.accounts.vault_token.amount,
+            ctx.accounts.global_payout_registry.total_pending,
+            referral_pending_in(config, &mint_key, &ctx.accounts.global_referral_registry),
+            entry_fees,
+        )?;
+
+        emit!(RacePayoutsRegisteredEvent {
+            race_id,
+            race_id_hash,
+            recipient_count: entries.len() as u32,
+            total_points,
+            total_amount,
+            mint: mint_key,
+            timestamp: now,
+        });
+        emit_race_status(&ctx.accounts.race, now);
+
+        attribute_registration(config, &mut ctx.accounts.season, &mint_key, total_amount, entries.len() as u32, now)?;
+
+        Ok(())
+    }
 
-    /// Claim all pending referral bonuses for a referrer using registry
-    /// This method reads the registry to get the total pending amount and transfers it
-    pub fn claim_pending_bonuses(ctx: Context<ClaimPendingBonuses>) -> Result<()> {
+    /// Post a merkle-root distribution for a race (operator only)
+    /// Leaves are hashv([0x00, index_le, recipient, points_le, amount_le]) and inner
+    /// nodes hashv([0x01, min(a, b), max(a, b)]); the total is reserved like a payout
+    /// and counts against the prize budget of the race, which must be Running
+    /// With `amounts_from_points` on, claims must match the points rate at posting
+    /// without a prize table. Leaves expire like payouts, after the claim window
+    pub fn post_race_distribution(
+        ctx: Context<PostRaceDistribution>,
+        race_id: String,
+        race_id_hash: [u8; 32],  // Client provides hash, we validate
+        merkle_root: [u8; 32],
+        total_amount: u64,
+        leaf_count: u32,
+    ) -> Result<()> {
         let config = &ctx.accounts.config;
         
         // Check if paused
         require!(!config.paused, VaultError::ProgramPaused);
+        require!(total_amount > 0, VaultError::ZeroAmount);
+        require!(
+            leaf_count > 0 && leaf_count <= MAX_DISTRIBUTION_LEAVES,
+            VaultError::InvalidLeafCount
+        );
         
-        let registry = &mut ctx.accounts.referrer_registry;
+        // Validate the hash matches the race_id
+        let computed_hash = hash(race_id.as_bytes()).to_bytes();
+        require!(
+            computed_hash == race_id_hash,
+            VaultError::InvalidRaceIdHash
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+
+        let distribution = &mut ctx.accounts.race_distribution;
+        distribution.race_id_hash = race_id_hash;
+        distribution.merkle_root = merkle_root;
+        distribution.total_amount = total_amount;
+        distribution.claimed_amount = 0;
+        distribution.leaf_count = leaf_count;
+        distribution.claimed_count = 0;
+        distribution.created_at = now;
+        distribution.season_id = registration_season(config, &ctx.accounts.season, now)?;
+        distribution.points_rate = if config.amounts_from_points { config.points_rate } else { 0 };
+        distribution.expires_at = config.payout_expiry(now)?;
+        distribution.claimed_bitmap = vec![0u8; RaceDistribution::bitmap_len(leaf_count)];
+
+        // Reserve the whole distribution in the global payout registry
+        ctx.accounts.global_payout_registry.record_payouts(total_amount, leaf_count, 0, now)?;
+        let fees_held = ctx.accounts.race.entry_fees_held();
+        ctx.accounts.race.record_payouts(total_amount, leaf_count)?;
+        let entry_fees = sync_entry_fees(
+            &ctx.accounts.global_entry_fee_registry,
+            fees_held,
+            ctx.accounts.race.entry_fees_held(),
+            now,
+        )?;
+        attribute_registration(config, &mut ctx.accounts.season, &ctx.accounts.mint.key(), total_amount, leaf_count, now)?;
+
+        // Reject distributions the vault balance cannot back
+        require_solvent(
+            config,
+            ctx.accounts.vault_token.amount,
+            ctx.accounts.global_payout_registry.total_pending,
+            referral_pending_in(config, &ctx.accounts.mint.key(), &ctx.accounts.global_referral_registry),
+            entry_fees,
+        )?;
+
+        emit!(RaceDistributionPostedEvent {
+            race_id,
+            race_id_hash,
+            merkle_root,
+            total_amount,
+            leaf_count,
+            mint: ctx.accounts.mint.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Claim one leaf of a race distribution with a merkle proof (anyone can call)
+    /// The leaf's points go to the recipient's lifetime and distribution-season ledgers
+    pub fn claim_distribution<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimDistribution<'info>>,
+        index: u32,
+        points: u64,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
         
-        // Check if there are pending bonuses
-        require!(registry.total_pending > 0, VaultError::NoPendingBonuses);
+        // Check if paused
+        require!(!config.paused, VaultError::ProgramPaused);
+        require!(amount > 0, VaultError::ZeroAmount);
+
+        let distribution = &mut ctx.accounts.race_distribution;
+        require!(index < distribution.leaf_count, VaultError::InvalidMerkleProof);
+        require!(!distribution.is_claimed(index), VaultError::DistributionAlreadyClaimed);
+
+        let recipient = ctx.accounts.recipient.key();
+        let leaf = hashv(&[
+            &[0u8],
+            &index.to_le_bytes(),
+            recipient.as_ref(),
+            &points.to_le_bytes(),
+            &amount.to_le_bytes(),
+        ])
+        .to_bytes();
+        require!(
+            verify_merkle_proof(&proof, distribution.merkle_root, leaf),
+            VaultError::InvalidMerkleProof
+        );
+
+        // Posted with amounts_from_points on: each leaf pays exactly its points' worth
+        if distribution.points_rate != 0 {
+            require!(
+                amount == points_amount(points, distribution.points_rate, ctx.accounts.mint.decimals)?,
+                VaultError::PointsAmountMismatch
+            );
+        }
+
+        // A bad tree must not pay out more than was reserved
+        let claimed_amount = distribution.claimed_amount.checked_add(amount).ok_or(VaultError::Overflow)?;
+        require!(claimed_amount <= distribution.total_amount, VaultError::DistributionExhausted);
         
         // Check vault has sufficient balance
         require!(
-            ctx.accounts.vault_token.amount >= registry.total_pending,
+            ctx.accounts.vault_token.amount >= amount,
             VaultError::InsufficientBalance
         );
 
-        let amount_to_claim = registry.total_pending;
-        let bonus_count = registry.bonus_count;
+        let now = Clock::get()?.unix_timestamp;
+        distribution.set_claimed(index);
+        distribution.claimed_amount = claimed_amount;
+        distribution.claimed_count += 1;
 
-        // Update registry
-        registry.total_pending = 0;
-        registry.total_claimed = registry.total_claimed.checked_add(amount_to_claim).ok_or(VaultError::Overflow)?;
-        registry.last_updated = Clock::get()?.unix_timestamp;
+        // Update global registry - move pending to claimed
+        let global_registry = &mut ctx.accounts.global_payout_registry;
+        global_registry.total_pending = global_registry.total_pending.checked_sub(amount).ok_or(VaultError::Overflow)?;
+        global_registry.total_claimed = global_registry.total_claimed.checked_add(amount).ok_or(VaultError::Overflow)?;
+        global_registry.last_updated = now;
+        let season_amount = if distribution.season_id == config.season_id { amount } else { 0 };
+        attribute_claim(config, &mut ctx.accounts.season, &ctx.accounts.mint.key(), season_amount)?;
+
+        // Leaf points count once claimed, towards the season the distribution was posted in
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        record_stats(config.key(), &ctx.accounts.racer_stats, recipient, 0, points, 0, &payer, &system_program, now)?;
+        if distribution.season_id != 0 {
+            let season_points = record_stats(
+                config.key(),
+                &ctx.accounts.season_stats,
+                recipient,
+                distribution.season_id,
+                points,
+                0,
+                &payer,
+                &system_program,
+                now,
+            )?;
+            if distribution.season_id == config.season_id {
+                update_leaderboard(distribution.season_id, &ctx.accounts.leaderboard, recipient, season_points)?;
+            }
+        }
 
-        // Transfer tokens to referrer
-        let cfg = &ctx.accounts.config;
-        let config_key = cfg.key();
+        // PDA signer seeds
+        let config_key = config.key();
         let seeds: &[&[u8]] = &[
             b"vault_signer",
             config_key.as_ref(),
-            &[cfg.vault_signer_bump],
+            &[config.vault_signer_bump],
         ];
         let signer = &[seeds];
 
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.vault_token.to_account_info(),
-            to: ctx.accounts.referrer_token.to_account_info(),
-            authority: ctx.accounts.vault_signer.to_account_info(),
-        };
-        let cpi_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            cpi_accounts,
+        transfer_checked_with_hook(
+            ctx.accounts.vault_token.to_account_info(),
+            &ctx.accounts.mint,
+            ctx.accounts.recipient_token.to_account_info(),
+            ctx.accounts.vault_signer.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
             signer,
-        );
-        token::transfer(cpi_ctx, amount_to_claim)?;
+        )?;
 
-        // Emit event
-        emit!(PendingBonusesClaimedEvent {
-            referrer: registry.referrer,
-            amount: amount_to_claim,
-            bonus_count,
-            timestamp: Clock::get()?.unix_timestamp,
+        emit!(DistributionClaimedEvent {
+            race_id_hash: distribution.race_id_hash,
+            index,
+            recipient,
+            points,
+            amount,
+            transfer_fee: transfer_fee(&ctx.accounts.mint, amount)?,
+            mint: ctx.accounts.mint.key(),
+            timestamp: now,
         });
 
         Ok(())
     }
 
-    /// Get pending bonuses for a referrer
-    /// This is a true read function that returns data directly
-    pub fn get_pending_bonuses(ctx: Context<GetPendingBonuses>) -> Result<ReferrerRegistry> {
-        let registry = &ctx.accounts.referrer_registry;
-        
-        // Return the registry data directly
-        Ok(ReferrerRegistry {
-            referrer: registry.referrer,
-            total_pending: registry.total_pending,
-            total_claimed: registry.total_claimed,
-            bonus_count: registry.bonus_count,
-            last_updated: registry.last_updated,
-        })
+    /// Close a race distribution once every leaf is claimed or it has expired (admin only)
+    /// Unclaimed leaves are released from the pending totals, and their race and open
+    /// season get the amount back; the rent goes to the authority
+    pub fn close_race_distribution(ctx: Context<CloseRaceDistribution>) -> Result<()> {
+        let distribution = &ctx.accounts.race_distribution;
+        let now = Clock::get()?.unix_timestamp;
+        let expired = distribution.expires_at != 0 && now >= distribution.expires_at;
+        require!(
+            expired || distribution.claimed_count == distribution.leaf_count,
+            VaultError::DistributionNotExpired
+        );
+
+        let released_amount = distribution.total_amount
+            .checked_sub(distribution.claimed_amount)
+            .ok_or(VaultError::Overflow)?;
+        let released_count = distribution.leaf_count
+            .checked_sub(distribution.claimed_count)
+            .ok_or(VaultError::Overflow)?;
+
+        let global_registry = &mut ctx.accounts.global_payout_registry;
+        global_registry.total_pending = global_registry.total_pending.checked_sub(released_amount).ok_or(VaultError::Overflow)?;
+        global_registry.last_updated = now;
+
+        let race = &mut ctx.accounts.race;
+        let fees_held = race.entry_fees_held();
+        race.settled_total = race.settled_total.checked_sub(released_amount).ok_or(VaultError::Overflow)?;
+        race.finisher_count = race.finisher_count.checked_sub(released_count).ok_or(VaultError::Overflow)?;
+        sync_entry_fees(&ctx.accounts.global_entry_fee_registry, fees_held, race.entry_fees_held(), now)?;
+
+        // The season gets the unclaimed budget back
+        if distribution.season_id != 0 {
+            let season = ctx.accounts.season.as_mut().ok_or(VaultError::SeasonAccountMissing)?;
+            season.release_registration(&ctx.accounts.mint.key(), released_amount, released_count)?;
+        }
+
+        emit!(RaceDistributionClosedEvent {
+            race_id_hash: distribution.race_id_hash,
+            released_amount,
+            released_count,
+            claimed_count: distribution.claimed_count,
+            mint: ctx.accounts.mint.key(),
+            timestamp: now,
+        });
+
+        Ok(())
     }
 
-    /// Get all bonuses (pending and claimed) for a referrer
-    /// This is a true read function that returns data directly
-    pub fn get_all_bonuses(ctx: Context<GetAllBonuses>) -> Result<ReferrerRegistry> {
-        let registry = &ctx.accounts.referrer_registry;
-        
-        // Return the registry data directly
-        Ok(ReferrerRegistry {
-            referrer: registry.referrer,
-            total_pending: registry.total_pending,
-            total_claimed: registry.total_claimed,
-            bonus_count: registry.bonus_count,
-            last_updated: registry.last_updated,
-        })
+    /// Cancel a registered payout that has not been claimed yet (admin only)
+    /// The receipt is kept with a Cancelled status so the payout cannot be replayed;
+    /// its race and open season, if any, get the amount back
+    pub fn cancel_payout(ctx: Context<CancelPayout>, race_id_hash: [u8; 32]) -> Result<()> {
+        let receipt = &mut ctx.accounts.payout_receipt;
+        let registry = &mut ctx.accounts.payout_registry;
+
+        require_unsettled_payout(receipt, registry)?;
+        require!(ctx.accounts.race.is_some() == receipt.has_race, VaultError::RaceAccountMismatch);
+
+        let now = Clock::get()?.unix_timestamp;
+        let amount = receipt.amount;
+        receipt.status = PayoutStatus::Cancelled;
+
+        // Reverse the receipt's contribution to the registries
+        registry.total_pending = registry.total_pending.checked_sub(amount).ok_or(VaultError::Overflow)?;
+        registry.amend_season(receipt.season_id, amount, 0)?;
+        registry.last_updated = now;
+
+        let global_registry = &mut ctx.accounts.global_payout_registry;
+        global_registry.total_pending = global_registry.total_pending.checked_sub(amount).ok_or(VaultError::Overflow)?;
+        global_registry.last_updated = now;
+
+        if let Some(race) = ctx.accounts.race.as_mut() {
+            let fees_held = race.entry_fees_held();
+            race.settled_total = race.settled_total.checked_sub(amount).ok_or(VaultError::Overflow)?;
+            race.finisher_count = race.finisher_count.checked_sub(1).ok_or(VaultError::Overflow)?;
+            sync_entry_fees(&ctx.accounts.global_entry_fee_registry, fees_held, race.entry_fees_held(), now)?;
+        }
+
+        // The season gets the budget back
+        if receipt.season_id != 0 {
+            let season = ctx.accounts.season.as_mut().ok_or(VaultError::SeasonAccountMissing)?;
+            season.release_registration(&ctx.accounts.mint.key(), amount, 1)?;
+        }
+
+        // Payouts registered against a race were recorded in the points ledgers
+        if receipt.has_race {
+            let racer_stats = ctx.accounts.racer_stats.as_mut().ok_or(VaultError::StatsAccountMissing)?;
+            racer_stats.remove_race(receipt.points)?;
+            if receipt.season_id != 0 {
+                let season_stats = ctx.accounts.season_stats.as_mut().ok_or(VaultError::StatsAccountMissing)?;
+                season_stats.remove_race(receipt.points)?;
+                if receipt.season_id == ctx.accounts.config.season_id {
+                    update_leaderboard(receipt.season_id, &ctx.accounts.leaderboard, receipt.recipient, season_stats.total_points)?;
+                }
+            }
+        }
+
+        emit!(PayoutCancelledEvent {
+            race_id_hash,
+            recipient: receipt.recipient,
+            amount,
+            mint: ctx.accounts.mint.key(),
+            timestamp: now,
+        });
+
+        Ok(())
     }
 
-    /// Get pending payouts for a recipient
-    /// This is a true read function that returns data directly
-    pub fn get_pending_payouts(ctx: Context<GetPendingPayouts>) -> Result<PayoutRegistry> {
-        let registry = &ctx.accounts.payout_registry;
-        
-        // Return the registry data directly
-        Ok(PayoutRegistry {
-            recipient: registry.recipient,
-            total_pending: registry.total_pending,
-            total_claimed: registry.total_claimed,
-            payout_count: registry.payout_count,
-            last_updated: registry.last_updated,
-        })
+    /// Amend the points and amount of a registered payout that has not been claimed yet (admin only)
+    /// Its race and open season, if any, follow the new amount within their budgets.
+    /// With `amounts_from_points` on, the new amount is derived from `new_points`.
+    /// SOL payouts are amended with amend_sol_payout
+    pub fn amend_payout(
+        ctx: Context<AmendPayout>,
+        race_id_hash: [u8; 32],
+        new_points: u64,
+        new_amount: u64,
+    ) -> Result<()> {
+        // Cancelling is the way to remove a payout entirely
+        let new_amount = payout_amount(&ctx.accounts.config, new_points, new_amount, ctx.accounts.mint.decimals)?;
+        require!(new_amount > 0, VaultError::ZeroAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let (old_points, old_amount) = amend_registered_payout(
+            &ctx.accounts.config,
+            &ctx.accounts.mint.key(),
+            &mut ctx.accounts.payout_receipt,
+            &mut ctx.accounts.payout_registry,
+            &mut ctx.accounts.global_payout_registry,
+            &mut ctx.accounts.race,
+            &mut ctx.accounts.season,
+            &mut ctx.accounts.racer_stats,
+            &mut ctx.accounts.season_stats,
+            &ctx.accounts.leaderboard,
+            &ctx.accounts.global_entry_fee_registry,
+            new_points,
+            new_amount,
+            now,
+        )?;
+
+        // Increases must still be backed by the vault balance
+        if new_amount > old_amount {
+            require_solvent(
+                &ctx.accounts.config,
+                ctx.accounts.vault_token.amount,
+                ctx.accounts.global_payout_registry.total_pending,
+                referral_pending_in(
+                    &ctx.accounts.config,
+                    &ctx.accounts.mint.key(),
+                    &ctx.accounts.global_referral_registry,
+                ),
+                entry_fees_held(&ctx.accounts.global_entry_fee_registry)?,
+            )?;
+        }
+
+        emit!(PayoutAmendedEvent {
+            race_id_hash,
+            recipient: ctx.accounts.payout_receipt.recipient,
+            old_points,
+            new_points,
+            old_amount,
+            new_amount,
+            mint: ctx.accounts.mint.key(),
+            timestamp: now,
+        });
+
+        Ok(())
     }
 
-    /// Get global payout statistics
-    /// This is a true read function that returns data directly
-    pub fn get_global_payout_stats(ctx: Context<GetGlobalPayoutStats>) -> Result<GlobalPayoutRegistry> {
-        let registry = &ctx.accounts.global_payout_registry;
+    /// Amend the points and lamport amount of a registered SOL payout that has not
+    /// been claimed yet (admin only); see amend_payout
+    pub fn amend_sol_payout(
+        ctx: Context<AmendSolPayout>,
+        race_id_hash: [u8; 32],
+        new_points: u64,
+        new_amount: u64,
+    ) -> Result<()> {
+        // Cancelling is the way to remove a payout entirely
+        let new_amount = payout_amount(&ctx.accounts.config, new_points, new_amount, SOL_DECIMALS)?;
+        require!(new_amount > 0, VaultError::ZeroAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let (old_points, old_amount) = amend_registered_payout(
+            &ctx.accounts.config,
+            &SOL_DENOMINATION,
+            &mut ctx.accounts.payout_receipt,
+            &mut ctx.accounts.payout_registry,
+            &mut ctx.accounts.global_payout_registry,
+            &mut ctx.accounts.race,
+            &mut ctx.accounts.season,
+            &mut ctx.accounts.racer_stats,
+            &mut ctx.accounts.season_stats,
+            &ctx.accounts.leaderboard,
+            &ctx.accounts.global_entry_fee_registry,
+            new_points,
+            new_amount,
+            now,
+        )?;
+
+        // Increases must still be backed by the SOL vault; referral bonuses and entry fees are never in SOL
+        if new_amount > old_amount {
+            require_solvent(
+                &ctx.accounts.config,
+                sol_vault_balance(&ctx.accounts.sol_vault)?,
+                ctx.accounts.global_payout_registry.total_pending,
+                0,
+                0,
+            )?;
+        }
+
+        emit!(PayoutAmendedEvent {
+            race_id_hash,
+            recipient: ctx.accounts.payout_receipt.recipient,
+            old_points,
+            new_points,
+            old_amount,
+            new_amount,
+            mint: SOL_DENOMINATION,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Release an expired, unclaimed payout back to the free pool (anyone can call)
+    /// Expired payouts stay claimable until they are swept. Like a cancellation, the
+    /// race and open season get the amount back; the racer keeps the points recorded
+    pub fn sweep_expired_payout(ctx: Context<SweepExpiredPayout>, race_id_hash: [u8; 32]) -> Result<()> {
+        let receipt = &mut ctx.accounts.payout_receipt;
+        let registry = &mut ctx.accounts.payout_registry;
+
+        require_unsettled_payout(receipt, registry)?;
+        require!(ctx.accounts.race.is_some() == receipt.has_race, VaultError::RaceAccountMismatch);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            receipt.expires_at != 0 && now >= receipt.expires_at,
+            VaultError::PayoutNotExpired
+        );
+
+        let amount = receipt.amount;
+        receipt.status = PayoutStatus::Expired;
+
+        // Remove the receipt's amount from the pending totals
+        registry.total_pending = registry.total_pending.checked_sub(amount).ok_or(VaultError::Overflow)?;
+        registry.amend_season(receipt.season_id, amount, 0)?;
+        registry.last_updated = now;
+
+        let global_registry = &mut ctx.accounts.global_payout_registry;
+        global_registry.total_pending = global_registry.total_pending.checked_sub(amount).ok_or(VaultError::Overflow)?;
+        global_registry.last_updated = now;
+
+        if let Some(race) = ctx.accounts.race.as_mut() {
+            let fees_held = race.entry_fees_held();
+            race.settled_total = race.settled_total.checked_sub(amount).ok_or(VaultError::Overflow)?;
+            race.finisher_count = race.finisher_count.checked_sub(1).ok_or(VaultError::Overflow)?;
+            sync_entry_fees(&ctx.accounts.global_entry_fee_registry, fees_held, race.entry_fees_held(), now)?;
+        }
+
+        // The season gets the budget back
+        if receipt.season_id != 0 {
+            let season = ctx.accounts.season.as_mut().ok_or(VaultError::SeasonAccountMissing)?;
+            season.release_registration(&ctx.accounts.mint.key(), amount, 1)?;
+        }
+
+        emit!(PayoutExpiredEvent {
+            race_id_hash,
+            recipient: receipt.recipient,
+            amount,
+            expired_at: receipt.expires_at,
+            mint: ctx.accounts.mint.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Close settled receipts of a race and return their rent to the authority (admin only)
+    /// remaining_accounts holds one (payout_receipt, payout_registry) pair per receipt.
+    /// A RaceTombstone replaces the receipts for replay protection, so no further
+    /// payouts can be registered for this race
+    pub fn close_receipts<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseReceipts<'info>>,
+        race_id_hash: [u8; 32],
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        require!(config.receipt_close_horizon > 0, VaultError::ReceiptClosingDisabled);
+        let pairs = ctx.remaining_accounts.chunks_exact(2);
+        require!(
+            !ctx.remaining_accounts.is_empty() && pairs.remainder().is_empty(),
+            VaultError::InvalidRemainingAccounts
+        );
+
+        let config_key = config.key();
+        let mint_seed = mint_seed(config, &ctx.accounts.mint.key());
+        let now = Clock::get()?.unix_timestamp;
+        let authority = ctx.accounts.authority.to_account_info();
+        let mut closed_count: u32 = 0;
+        let mut lamports_reclaimed: u64 = 0;
+
+        for accounts in pairs {
+            let (receipt_info, registry_info) = (&accounts[0], &accounts[1]);
+            require!(receipt_info.is_writable, VaultError::InvalidRemainingAccounts);
+
+            let receipt = Account::<PayoutReceipt>::try_from(receipt_info)?;
+            require!(receipt.race_id_hash == race_id_hash, VaultError::InvalidRemainingAccounts);
+            let (receipt_key, _) = Pubkey::find_program_address(
+                &[b"receipt", config_key.as_ref(), &race_id_hash, receipt.recipient.as_ref(), &mint_seed],
+                &crate::ID,
+            );
+            require!(receipt_info.key() == receipt_key, VaultError::InvalidRemainingAccounts);
+
+            let registry = Account::<PayoutRegistry>::try_from(registry_info)?;
+            let (registry_key, _) = Pubkey::find_program_address(
+                &[b"payout_registry", config_key.as_ref(), receipt.recipient.as_ref(), &mint_seed],
+                &crate::ID,
+            );
+            require!(registry_info.key() == registry_key, VaultError::InvalidRemainingAccounts);
+
+            // Only receipts past the horizon whose amount has left the pending totals
+            let closable_at = receipt.timestamp
+                .checked_add(config.receipt_close_horizon)
+                .ok_or(VaultError::Overflow)?;
+            require!(now >= closable_at, VaultError::ReceiptTooRecent);
+            let settled = matches!(receipt.status, PayoutStatus::Cancelled | PayoutStatus::Expired)
+                || receipt.sequence < registry.claimed_count;
+            require!(settled, VaultError::PayoutNotSettled);
+
+            lamports_reclaimed = lamports_reclaimed.checked_add(receipt_info.lamports()).ok_or(VaultError::Overflow)?;
+            receipt.close(authority.clone())?;
+            closed_count += 1;
+        }
+
+        // Tombstone keeps the race from being registered again
+        let tombstone = &mut ctx.accounts.race_tombstone;
+        tombstone.race_id_hash = race_id_hash;
+        tombstone.closed_count = tombstone.closed_count.checked_add(closed_count).ok_or(VaultError::Overflow)?;
+        tombstone.closed_at = now;
+
+        emit!(ReceiptsClosedEvent {
+            race_id_hash,
+            closed_count,
+            lamports_reclaimed,
+            mint: ctx.accounts.mint.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Claim all pending payouts for a wallet (anyone can call)
+    /// Transfers all pending payouts to the recipient's token account
+    pub fn claim_pending_payouts<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimPendingPayouts<'info>>,
+        recipient: Pubkey,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
         
-        // Return the global registry data directly
-        Ok(GlobalPayoutRegistry {
-            total_pending: registry.total_pending,
-            total_claimed: registry.total_claimed,
-            total_payout_count: registry.total_payout_count,
-            total_recipient_count: registry.total_recipient_count,
-            last_updated: registry.last_updated,
-        })
+        // Check if paused
+        require!(!config.paused, VaultError::ProgramPaused);
+        
+        // Validate recipient matches the account
+        require!(
+            recipient == ctx.accounts.recipient.key(),
+            VaultError::InvalidRecipient
+        );
+
+        // Get pending payouts from registry
+        let registry = &mut ctx.accounts.payout_registry;
+        
+        // Check if there are pending payouts
+        require!(registry.total_pending > 0, VaultError::NoPendingPayouts);
+        
+        // Check vault has sufficient balance
+        require!(
+            ctx.accounts.vault_token.amount >= registry.total_pending,
+            VaultError::InsufficientBalance
+        );
+
+        let total_pending = registry.total_pending;
+        let payout_count = registry.payout_count;
+
+        // Update registry - move pending to claimed
+        // Every receipt registered so far is now settled
+        registry.total_pending = 0;
+        registry.claimed_count = payout_count;
+        registry.total_claimed = registry.total_claimed.checked_add(total_pending).ok_or(VaultError::Overflow)?;
+        registry.last_updated = Clock::get()?.unix_timestamp;
+        let season_amount = registry.take_season_pending(config.season_id);
+        
+        // Update global registry - move pending to claimed
+        let global_registry = &mut ctx.accounts.global_payout_registry;
+        global_registry.total_pending = global_registry.total_pending.checked_sub(total_pending).ok_or(VaultError::Overflow)?;
+        global_registry.total_claimed = global_registry.total_claimed.checked_add(total_pending).ok_or(VaultError::Overflow)?;
+        global_registry.last_updated = Clock::get()?.unix_timestamp;
+        attribute_claim(config, &mut ctx.accounts.season, &ctx.accounts.mint.key(), season_amount)?;
+
+        // Transfer from vault (PDA signer) to recipient ATA
+        let cfg = &ctx.accounts.config;
+
+        // PDA signer seeds
+        let config_key = cfg.key();
+        let seeds: &[&[u8]] = &[
+            b"vault_signer",
+            config_key.as_ref(),
+            &[cfg.vault_signer_bump],
+        ];
+        
+        let signer = &[seeds];
+
+        transfer_checked_with_hook(
+            ctx.accounts.vault_token.to_account_info(),
+            &ctx.accounts.mint,
+            ctx.accounts.recipient_token.to_account_info(),
+            ctx.accounts.vault_signer.to_account_info(),
+            ctx.remaining_accounts,
+            total_pending,
+            signer,
+        )?;
+
+        // Emit for off-chain indexing
+        emit!(PayoutsClaimedEvent {
+            recipient: registry.recipient,
+            total_amount: total_pending,
+            transfer_fee: transfer_fee(&ctx.accounts.mint, total_pending)?,
+            payout_count,
+            mint: ctx.accounts.mint.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Claim all pending lamport payouts for a wallet (anyone can call)
+    /// Transfers all pending SOL payouts from the SOL vault to the recipient
+    pub fn claim_pending_sol_payouts(
+        ctx: Context<ClaimPendingSolPayouts>,
+        recipient: Pubkey,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+
+        // Check if paused
+        require!(!config.paused, VaultError::ProgramPaused);
+
+        // Validate recipient matches the account
+        require!(
+            recipient == ctx.accounts.recipient.key(),
+            VaultError::InvalidRecipient
+        );
+
+        // Get pending payouts from registry
+        let registry = &mut ctx.accounts.payout_registry;
+
+        // Check if there are pending payouts
+        require!(registry.total_pending > 0, VaultError::NoPendingPayouts);
+
+        // Check the SOL vault can pay without dropping below rent exemption
+        require!(
+            sol_vault_balance(&ctx.accounts.sol_vault)? >= registry.total_pending,
+            VaultError::InsufficientBalance
+        );
+
+        let total_pending = registry.total_pending;
+        let payout_count = registry.payout_count;
+        let now = Clock::get()?.unix_timestamp;
+
+        // Update registry - move pending to claimed
+        // Every receipt registered so far is now settled
+        registry.total_pending = 0;
+        registry.claimed_count = payout_count;
+        registry.total_claimed = registry.total_claimed.checked_add(total_pending).ok_or(VaultError::Overflow)?;
+        registry.last_updated = now;
+        let season_amount = registry.take_season_pending(config.season_id);
+
+        // Update global registry - move pending to claimed
+        let global_registry = &mut ctx.accounts.global_payout_registry;
+        global_registry.total_pending = global_registry.total_pending.checked_sub(total_pending).ok_or(VaultError::Overflow)?;
+        global_registry.total_claimed = global_registry.total_claimed.checked_add(total_pending).ok_or(VaultError::Overflow)?;
+        global_registry.last_updated = now;
+        attribute_claim(config, &mut ctx.accounts.season, &SOL_DENOMINATION, season_amount)?;
+
+        transfer_sol_from_vault(
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.sol_vault.to_account_info(),
+            ctx.accounts.recipient.to_account_info(),
+            config.key(),
+            ctx.bumps.sol_vault,
+            total_pending,
+        )?;
+
+        // Emit for off-chain indexing
+        emit!(PayoutsClaimedEvent {
+            recipient: registry.recipient,
+            total_amount: total_pending,
+            transfer_fee: 0,
+            payout_count,
+            mint: SOL_DENOMINATION,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a payout signed off-chain by the voucher signer (recipient calls)
+    /// The instruction right before this one must be an ed25519 precompile check of
+    /// `voucher_message(...)` signed by `Config.voucher_signer`. Nothing is registered:
+    /// the amount is paid straight from the unreserved vault balance, and the nonce
+    /// can only ever be used once. The voucher's points go to the recipient's ledgers
+    pub fn claim_with_voucher<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimWithVoucher<'info>>,
+        race_id_hash: [u8; 32],
+        points: u64,
+        amount: u64,
+        expiry: i64,
+        nonce: u64,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+
+        // Check if paused
+        require!(!config.paused, VaultError::ProgramPaused);
+        require!(amount > 0, VaultError::ZeroAmount);
+        require!(config.voucher_signer != Pubkey::default(), VaultError::VouchersDisabled);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(now <= expiry, VaultError::VoucherExpired);
+
+        let config_key = config.key();
+        let mint_key = ctx.accounts.mint.key();
+        let recipient = ctx.accounts.recipient.key();
+        let message = voucher_message(&config_key, &mint_key, &race_id_hash, &recipient, points, amount, expiry, nonce);
+        verify_ed25519_signature(&ctx.accounts.instructions, &config.voucher_signer, &message)?;
+
+        // Vouchers draw only on the balance not reserved for registered obligations
+        let balance_after = ctx.accounts.vault_token.amount
+            .checked_sub(amount)
+            .ok_or(VaultError::InsufficientBalance)?;
+        require_solvent(
+            config,
+            balance_after,
+            ctx.accounts.global_payout_registry.total_pending,
+            referral_pending_in(config, &mint_key, &ctx.accounts.global_referral_registry),
+            entry_fees_held(&ctx.accounts.global_entry_fee_registry)?,
+        )?;
+
+        // Record the nonce (its creation is the replay protection)
+        let voucher_claim = &mut ctx.accounts.voucher_claim;
+        voucher_claim.race_id_hash = race_id_hash;
+        voucher_claim.recipient = recipient;
+        voucher_claim.mint = mint_key;
+        voucher_claim.amount = amount;
+        voucher_claim.claimed_at = now;
+
+        // A voucher is registered and claimed at once
+        let season_id = registration_season(config, &ctx.accounts.season, now)?;
+        let season_amount = if season_id != 0 { amount } else { 0 };
+        attribute_registration(config, &mut ctx.accounts.season, &mint_key, amount, 1, now)?;
+        attribute_claim(config, &mut ctx.accounts.season, &mint_key, season_amount)?;
+
+        // Points ledgers, like any other race result
+        let payer = ctx.accounts.recipient.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        record_stats(config_key, &ctx.accounts.racer_stats, recipient, 0, points, 0, &payer, &system_program, now)?;
+        if season_id != 0 {
+            let season_points = record_stats(
+                config_key,
+                &ctx.accounts.season_stats,
+                recipient,
+                season_id,
+                points,
+                0,
+                &payer,
+                &system_program,
+                now,
+            )?;
+            update_leaderboard(season_id, &ctx.accounts.leaderboard, recipient, season_points)?;
+        }
+
+        // PDA signer seeds
+        let seeds: &[&[u8]] = &[
+            b"vault_signer",
+            config_key.as_ref(),
+            &[config.vault_signer_bump],
+        ];
+        let signer = &[seeds];
+
+        transfer_checked_with_hook(
+            ctx.accounts.vault_token.to_account_info(),
+            &ctx.accounts.mint,
+            ctx.accounts.recipient_token.to_account_info(),
+            ctx.accounts.vault_signer.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            signer,
+        )?;
+
+        emit!(VoucherClaimedEvent {
+            race_id_hash,
+            recipient,
+            points,
+            amount,
+            transfer_fee: transfer_fee(&ctx.accounts.mint, amount)?,
+            nonce,
+            mint: mint_key,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Propose a new admin (current authority only)
+    /// The handover only completes once the proposed key signs accept_authority
+    pub fn transfer_authority(
+        ctx: Context<TransferAuthority>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        require!(new_authority != Pubkey::default(), VaultError::InvalidAuthority);
+
+        let config = &mut ctx.accounts.config;
+        config.pending_authority = new_authority;
+
+        emit!(AuthorityTransferProposedEvent {
+            authority: config.authority,
+            pending_authority: new_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Accept a proposed authority transfer (pending authority only)
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let old_authority = config.authority;
+        config.authority = ctx.accounts.pending_authority.key();
+        config.pending_authority = Pubkey::default();
+
+        emit!(AuthorityTransferAcceptedEvent {
+            old_authority,
+            new_authority: config.authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a proposed authority transfer (current authority only)
+    pub fn cancel_authority_transfer(ctx: Context<CancelAuthorityTransfer>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            config.pending_authority != Pubkey::default(),
+            VaultError::NoPendingAuthority
+        );
+
+        let cancelled_authority = config.pending_authority;
+        config.pending_authority = Pubkey::default();
+
+        emit!(AuthorityTransferCancelledEvent {
+            authority: config.authority,
+            cancelled_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Grant a role to a key (admin only)
+    pub fn grant_role(ctx: Context<ManageRole>, role: Role, holder: Pubkey) -> Result<()> {
+        require!(holder != Pubkey::default(), VaultError::InvalidRoleHolder);
+
+        let config = &mut ctx.accounts.config;
+        let previous_holder = config.role_holder(role);
+        config.set_role_holder(role, holder);
+
+        emit!(RoleGrantedEvent {
+            role,
+            holder,
+            previous_holder,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke a role (admin only) - leaves the role unassigned until granted again
+    pub fn revoke_role(ctx: Context<ManageRole>, role: Role) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let previous_holder = config.role_holder(role);
+        require!(previous_holder != Pubkey::default(), VaultError::RoleNotAssigned);
+        config.set_role_holder(role, Pubkey::default());
+
+        emit!(RoleRevokedEvent {
+            role,
+            previous_holder,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
     }
+
+    /// Update config parameters
+    /// Pausing requires the pauser role, every other parameter requires the admin
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        paused: Option<bool>,
+        reserve_ratio_bps: Option<u16>,
+        claim_window: Option<i64>,
+        receipt_close_horizon: Option<i64>,
+        voucher_signer: Option<Pubkey>,
+        points_rate: Option<u64>,
+        amounts_from_points: Option<bool>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let signer = ctx.accounts.signer.key();
+
+        if let Some(pause_state) = paused {
+            require!(config.has_role(Role::Pauser, &signer), VaultError::Unauthorized);
+            config.paused = pause_state;
+        }
+
+        if let Some(ratio) = reserve_ratio_bps {
+            require!(config.authority == signer, VaultError::Unauthorized);
+            require!(ratio <= BPS_DENOMINATOR, VaultError::InvalidReserveRatio);
+            config.reserve_ratio_bps = ratio;
+        }
+
+        if let Some(window) = claim_window {
+            require!(config.authority == signer, VaultError::Unauthorized);
+            require!(window >= 0, VaultError::InvalidClaimWindow);
+            config.claim_window = window;
+        }
+
+        if let Some(horizon) = receipt_close_horizon {
+            require!(config.authority == signer, VaultError::Unauthorized);
+            require!(horizon >= 0, VaultError::InvalidCloseHorizon);
+            config.receipt_close_horizon = horizon;
+        }
+
+        if let Some(key) = voucher_signer {
+            require!(config.authority == signer, VaultError::Unauthorized);
+            config.voucher_signer = key;
+        }
+
+        if let Some(rate) = points_rate {
+            require!(config.authority == signer, VaultError::Unauthorized);
+            let old_rate = config.points_rate;
+            config.points_rate = rate;
+
+            emit!(PointsRateUpdatedEvent {
+                old_rate,
+                new_rate: rate,
+                rate_decimals: POINTS_RATE_DECIMALS,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+
+        if let Some(derive) = amounts_from_points {
+            require!(config.authority == signer, VaultError::Unauthorized);
+            config.amounts_from_points = derive;
+        }
+
+        // Deriving amounts needs a rate to derive them with
+        require!(
+            !config.amounts_from_points || config.points_rate > 0,
+            VaultError::InvalidPointsRate
+        );
+
+        emit!(ConfigUpdateEvent {
+            paused: config.paused,
+            reserve_ratio_bps: config.reserve_ratio_bps,
+            claim_window: config.claim_window,
+            receipt_close_horizon: config.receipt_close_horizon,
+            voucher_signer: config.voucher_signer,
+            points_rate: config.points_rate,
+            amounts_from_points: config.amounts_from_points,
+            season_id: config.season_id,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Tear down the vault (admin only) - for reinitialization or cleanup
+    /// Requires every secondary mint to be removed first and no pending payouts, bonuses or held entry fees; sends the remaining vault balance to
+    /// the treasury account, then closes the vault token account, both global
+    /// registries and finally config, returning their rent to the authority
+    pub fn decommission<'info>(ctx: Context<'_, '_, '_, 'info, Decommission<'info>>) -> Result<()> {
+        require!(
+            ctx.accounts.config.accepted_mints.is_empty(),
+            VaultError::MintsStillAccepted
+        );
+        require!(
+            ctx.accounts.global_payout_registry.total_pending == 0,
+            VaultError::OutstandingObligations
+        );
+        require!(
+            ctx.accounts.global_referral_registry.total_pending == 0,
+            VaultError::OutstandingObligations
+        );
+        require!(
+            entry_fees_held(&ctx.accounts.global_entry_fee_registry)? == 0,
+            VaultError::OutstandingObligations
+        );
+
+        // PDA signer seeds
+        let cfg = &ctx.accounts.config;
+        let config_key = cfg.key();
+        let seeds: &[&[u8]] = &[
+            b"vault_signer",
+            config_key.as_ref(),
+            &[cfg.vault_signer_bump],
+        ];
+        let signer = &[seeds];
+
+        // Sweep the remaining balance to the treasury
+        let amount_swept = ctx.accounts.vault_token.amount;
+        if amount_swept > 0 {
+            transfer_checked_with_hook(
+                ctx.accounts.vault_token.to_account_info(),
+                &ctx.accounts.mint,
+                ctx.accounts.treasury_token.to_account_info(),
+                ctx.accounts.vault_signer.to_account_info(),
+                ctx.remaining_accounts,
+                amount_swept,
+                signer,
+            )?;
+        }
+
+        // Token-2022 fees withheld in the vault account would block closing it
+        if has_withheld_fees(&ctx.accounts.vault_token)? {
+            let harvest_ix = harvest_withheld_tokens_to_mint(
+                &spl_token_2022::ID,
+                &ctx.accounts.mint.key(),
+                &[&ctx.accounts.vault_token.key()],
+            )?;
+            anchor_lang::solana_program::program::invoke(
+                &harvest_ix,
+                &[
+                    ctx.accounts.mint.to_account_info(),
+                    ctx.accounts.vault_token.to_account_info(),
+                ],
+            )?;
+        }
+
+        // Close the now empty vault token account
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.vault_token.to_account_info(),
+            destination: ctx.accounts.authority.to_account_info(),
+            authority: ctx.accounts.vault_signer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        );
+        token_interface::close_account(cpi_ctx)?;
+
+        // Registries and config are closed on exit via their `close` constraints
+        emit!(VaultDecommissionedEvent {
+            authority: ctx.accounts.authority.key(),
+            treasury_token: ctx.accounts.treasury_token.key(),
+            amount_swept,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Allow payouts in an additional mint (admin only)
+    /// Creates the vault ATA and the global payout registry for that mint;
+    /// referral bonuses stay in the primary mint
+    pub fn add_mint(ctx: Context<AddMint>) -> Result<()> {
+        let mint_key = ctx.accounts.mint.key();
+        let config = &mut ctx.accounts.config;
+        require!(!config.accepts_mint(&mint_key), VaultError::MintAlreadyAccepted);
+        require!(config.accepted_mints.len() < MAX_ACCEPTED_MINTS, VaultError::TooManyMints);
+        config.accepted_mints.push(mint_key);
+
+        let global_registry = &mut ctx.accounts.global_payout_registry;
+        global_registry.total_pending = 0;
+        global_registry.total_claimed = 0;
+        global_registry.total_payout_count = 0;
+        global_registry.total_recipient_count = 0;
+        global_registry.last_updated = Clock::get()?.unix_timestamp;
+
+        emit!(MintAddedEvent {
+            authority: ctx.accounts.authority.key(),
+            mint: mint_key,
+            vault_token: ctx.accounts.vault_token.key(),
+            timestamp: global_registry.last_updated,
+        });
+        Ok(())
+    }
+
+    /// Stop accepting a secondary mint (admin only)
+    /// Requires no pending payouts or held entry fees in that mint, sends its remaining vault balance to
+    /// the treasury account, then closes its vault token account and global registry
+    pub fn remove_mint<'info>(ctx: Context<'_, '_, '_, 'info, RemoveMint<'info>>) -> Result<()> {
+        require!(
+            ctx.accounts.global_payout_registry.total_pending == 0,
+            VaultError::OutstandingObligations
+        );
+        require!(
+            entry_fees_held(&ctx.accounts.global_entry_fee_registry)? == 0,
+            VaultError::OutstandingObligations
+        );
+
+        // PDA signer seeds
+        let cfg = &ctx.accounts.config;
+        let config_key = cfg.key();
+        let seeds: &[&[u8]] = &[
+            b"vault_signer",
+            config_key.as_ref(),
+            &[cfg.vault_signer_bump],
+        ];
+        let signer = &[seeds];
+
+        // Sweep the remaining balance to the treasury
+        let amount_swept = ctx.accounts.vault_token.amount;
+        if amount_swept > 0 {
+            transfer_checked_with_hook(
+                ctx.accounts.vault_token.to_account_info(),
+                &ctx.accounts.mint,
+                ctx.accounts.treasury_token.to_account_info(),
+                ctx.accounts.vault_signer.to_account_info(),
+                ctx.remaining_accounts,
+                amount_swept,
+                signer,
+            )?;
+        }
+
+        // Token-2022 fees withheld in the vault account would block closing it
+        if has_withheld_fees(&ctx.accounts.vault_token)? {
+            let harvest_ix = harvest_withheld_tokens_to_mint(
+                &spl_token_2022::ID,
+                &ctx.accounts.mint.key(),
+                &[&ctx.accounts.vault_token.key()],
+            )?;
+            anchor_lang::solana_program::program::invoke(
+                &harvest_ix,
+                &[
+                    ctx.accounts.mint.to_account_info(),
+                    ctx.accounts.vault_token.to_account_info(),
+                ],
+            )?;
+        }
+
+        // Close the now empty vault token account
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.vault_token.to_account_info(),
+            destination: ctx.accounts.authority.to_account_info(),
+            authority: ctx.accounts.vault_signer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        );
+        token_interface::close_account(cpi_ctx)?;
+
+        // The global registry is closed on exit via its `close` constraint
+        let mint_key = ctx.accounts.mint.key();
+        ctx.accounts.config.accepted_mints.retain(|accepted| *accepted != mint_key);
+
+        emit!(MintRemovedEvent {
+            authority: ctx.accounts.authority.key(),
+            mint: mint_key,
+            treasury_token: ctx.accounts.treasury_token.key(),
+            amount_swept,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Allow payouts in native SOL (admin only)
+    /// SOL is tracked as the accepted mint SOL_DENOMINATION, backed by the lamport
+    /// pool at the `sol_vault` PDA instead of a token account
+    pub fn enable_sol(ctx: Context<EnableSol>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(!config.accepts_mint(&SOL_DENOMINATION), VaultError::MintAlreadyAccepted);
+        require!(config.accepted_mints.len() < MAX_ACCEPTED_MINTS, VaultError::TooManyMints);
+        config.accepted_mints.push(SOL_DENOMINATION);
+
+        let global_registry = &mut ctx.accounts.global_payout_registry;
+        global_registry.total_pending = 0;
+        global_registry.total_claimed = 0;
+        global_registry.total_payout_count = 0;
+        global_registry.total_recipient_count = 0;
+        global_registry.last_updated = Clock::get()?.unix_timestamp;
+
+        emit!(MintAddedEvent {
+            authority: ctx.accounts.authority.key(),
+            mint: SOL_DENOMINATION,
+            vault_token: ctx.accounts.sol_vault.key(),
+            timestamp: global_registry.last_updated,
+        });
+        Ok(())
+    }
+
+    /// Stop accepting native SOL (admin only)
+    /// Requires no pending SOL payouts, sends every lamport in the SOL vault to the
+    /// treasury wallet, then closes the SOL global registry
+    pub fn disable_sol(ctx: Context<DisableSol>) -> Result<()> {
+        require!(
+            ctx.accounts.global_payout_registry.total_pending == 0,
+            VaultError::OutstandingObligations
+        );
+
+        // Sweep the whole pool, including its rent-exempt minimum
+        let amount_swept = ctx.accounts.sol_vault.lamports();
+        if amount_swept > 0 {
+            transfer_sol_from_vault(
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.sol_vault.to_account_info(),
+                ctx.accounts.treasury.to_account_info(),
+                ctx.accounts.config.key(),
+                ctx.bumps.sol_vault,
+                amount_swept,
+            )?;
+        }
+
+        // The global registry is closed on exit via its `close` constraint
+        ctx.accounts.config.accepted_mints.retain(|accepted| *accepted != SOL_DENOMINATION);
+
+        emit!(MintRemovedEvent {
+            authority: ctx.accounts.authority.key(),
+            mint: SOL_DENOMINATION,
+            treasury_token: ctx.accounts.treasury.key(),
+            amount_swept,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Register referral bonus (operator only)
+    /// Creates a referral bonus record for a specific race, referrer, and referee
+    pub fn register_referral_bonus(
+        ctx: Context<RegisterReferralBonus>,
+        race_id: String,
+        referrer: Pubkey,
+        referee: Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        
+        // Check if paused
+        require!(!config.paused, VaultError::ProgramPaused);
+        require!(amount > 0, VaultError::ZeroAmount);
+        
+        // Validate race_id length (Solana PDA seeds max 32 bytes)
+        require!(race_id.len() <= 32, VaultError::RaceIdTooLong);
+        
+        // Prevent self-referrals
+        require!(referrer != referee, VaultError::SelfReferralNotAllowed);
+
+        // Create referral bonus record
+        let referral_bonus = &mut ctx.accounts.referral_bonus;
+        referral_bonus.race_id = race_id;
+        referral_bonus.referrer = referrer;
+        referral_bonus.referee = referee;
+        referral_bonus.amount = amount;
+        referral_bonus.claimed = false;
+        referral_bonus.timestamp = Clock::get()?.unix_timestamp;
+        referral_bonus.claimed_at = 0;
+
+        // Update referrer registry
+        let registry = &mut ctx.accounts.referrer_registry;
+        
+        // Track if this is a new referrer for global stats
+        let is_new_referrer = registry.referrer == Pubkey::default();
+        
+        // Initialize registry if this is the first bonus (H-01 fix)
+        if is_new_referrer {
+            registry.referrer = referrer;
+        }
+        
+        registry.total_pending = registry.total_pending.checked_add(amount).ok_or(VaultError::Overflow)?;
+        registry.bonus_count += 1;
+        registry.last_updated = Clock::get()?.unix_timestamp;
+
+        // Update global referral registry
+        let global_registry = &mut ctx.accounts.global_referral_registry;
+        global_registry.total_pending = global_registry.total_pending.checked_add(amount).ok_or(VaultError::Overflow)?;
+        global_registry.total_bonus_count += 1;
+        if is_new_referrer {
+            global_registry.total_referrer_count += 1;
+        }
+        global_registry.last_updated = Clock::get()?.unix_timestamp;
+
+        // Reject registrations the vault balance cannot back
+        require_solvent(
+            config,
+            ctx.accounts.vault_token.amount,
+            ctx.accounts.global_payout_registry.total_pending,
+            global_registry.total_pending,
+            entry_fees_held(&ctx.accounts.global_entry_fee_registry)?,
+        )?;
+
+        // Emit event for off-chain tracking
+        emit!(ReferralBonusRegisteredEvent {
+            race_id: referral_bonus.race_id.clone(),
+            referrer: referral_bonus.referrer,
+            referee: referral_bonus.referee,
+            amount: referral_bonus.amount,
+            timestamp: referral_bonus.timestamp,
+        });
+
+        Ok(())
+    }
+
+
+    /// Claim referral bonuses for a referrer by settling individual bonus records
+    /// The ReferralBonus accounts to settle are passed as writable remaining accounts,
+    /// followed by any transfer-hook accounts; each bonus is marked claimed and the
+    /// registry totals move by exactly their sum
+    pub fn claim_pending_bonuses<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimPendingBonuses<'info>>,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        
+        // Check if paused
+        require!(!config.paused, VaultError::ProgramPaused);
+        
+        let registry = &mut ctx.accounts.referrer_registry;
+        
+        // Check if there are pending bonuses
+        require!(registry.total_pending > 0, VaultError::NoPendingBonuses);
+
+        // Leading program-owned accounts are bonus records, the rest go to the transfer hook
+        let bonus_len = ctx.remaining_accounts
+            .iter()
+            .take_while(|info| info.owner == &crate::ID)
+            .count();
+        let (bonus_accounts, hook_accounts) = ctx.remaining_accounts.split_at(bonus_len);
+        require!(!bonus_accounts.is_empty(), VaultError::NoPendingBonuses);
+
+        let config_key = config.key();
+        let now = Clock::get()?.unix_timestamp;
+        let mut amount_to_claim: u64 = 0;
+        let mut bonus_count: u32 = 0;
+
+        // Settle each bonus record
+        for bonus_info in bonus_accounts.iter() {
+            // Legacy records may already be paid; migrate_referral_bonuses settles them first
+            require!(
+                bonus_info.is_writable && bonus_info.data_len() == 8 + ReferralBonus::SIZE,
+                VaultError::InvalidReferralBonus
+            );
+            let mut bonus = Account::<ReferralBonus>::try_from(bonus_info)?;
+
+            // Bonus must belong to this config and this referrer
+            let (expected_key, _) = Pubkey::find_program_address(
+                &[
+                    b"referral_bonus",
+                    config_key.as_ref(),
+                    bonus.race_id.as_bytes(),
+                    bonus.referrer.as_ref(),
+                    bonus.referee.as_ref(),
+                ],
+                &crate::ID,
+            );
+            require!(bonus_info.key() == expected_key, VaultError::InvalidReferralBonus);
+            require!(bonus.referrer == registry.referrer, VaultError::InvalidReferralBonus);
+            require!(!bonus.claimed, VaultError::BonusAlreadyClaimed);
+
+            bonus.claimed = true;
+            bonus.claimed_at = now;
+            // Persist immediately so a duplicated account is seen as claimed
+            bonus.exit(&crate::ID)?;
+
+            amount_to_claim = amount_to_claim.checked_add(bonus.amount).ok_or(VaultError::Overflow)?;
+            bonus_count += 1;
+
+            emit!(ReferralBonusClaimedEvent {
+                bonus: bonus_info.key(),
+                race_id: bonus.race_id.clone(),
+                referrer: bonus.referrer,
+                referee: bonus.referee,
+                amount: bonus.amount,
+                timestamp: now,
+            });
+        }
+        
+        // Check vault has sufficient balance
+        require!(
+            ctx.accounts.vault_token.amount >= amount_to_claim,
+            VaultError::InsufficientBalance
+        );
+
+        // Update registry
+        registry.total_pending = registry.total_pending.checked_sub(amount_to_claim).ok_or(VaultError::Overflow)?;
+        registry.total_claimed = registry.total_claimed.checked_add(amount_to_claim).ok_or(VaultError::Overflow)?;
+        registry.last_updated = now;
+
+        // Update global referral registry - move pending to claimed
+        let global_registry = &mut ctx.accounts.global_referral_registry;
+        global_registry.total_pending = global_registry.total_pending.checked_sub(amount_to_claim).ok_or(VaultError::Overflow)?;
+        global_registry.total_claimed = global_registry.total_claimed.checked_add(amount_to_claim).ok_or(VaultError::Overflow)?;
+        global_registry.last_updated = now;
+
+        // Transfer tokens to referrer
+        let cfg = &ctx.accounts.config;
+        let seeds: &[&[u8]] = &[
+            b"vault_signer",
+            config_key.as_ref(),
+            &[cfg.vault_signer_bump],
+        ];
+        let signer = &[seeds];
+
+        transfer_checked_with_hook(
+            ctx.accounts.vault_token.to_account_info(),
+            &ctx.accounts.mint,
+            ctx.accounts.referrer_token.to_account_info(),
+            ctx.accounts.vault_signer.to_account_info(),
+            hook_accounts,
+            amount_to_claim,
+            signer,
+        )?;
+
+        // Emit event
+        emit!(PendingBonusesClaimedEvent {
+            referrer: registry.referrer,
+            amount: amount_to_claim,
+            transfer_fee: transfer_fee(&ctx.accounts.mint, amount_to_claim)?,
+            bonus_count,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Get pending bonuses for a referrer
+    /// This is a true read function that returns data directly
+    pub fn get_pending_bonuses(ctx: Context<GetPendingBonuses>) -> Result<ReferrerRegistry> {
+        let registry = &ctx.accounts.referrer_registry;
+        
+        // Return the registry data directly
+        Ok(ReferrerRegistry {
+            referrer: registry.referrer,
+            total_pending: registry.total_pending,
+            total_claimed: registry.total_claimed,
+            bonus_count: registry.bonus_count,
+            last_updated: registry.last_updated,
+        })
+    }
+
+    /// Get all bonuses (pending and claimed) for a referrer
+    /// This is a true read function that returns data directly
+    pub fn get_all_bonuses(ctx: Context<GetAllBonuses>) -> Result<ReferrerRegistry> {
+        let registry = &ctx.accounts.referrer_registry;
+        
+        // Return the registry data directly
+        Ok(ReferrerRegistry {
+            referrer: registry.referrer,
+            total_pending: registry.total_pending,
+            total_claimed: registry.total_claimed,
+            bonus_count: registry.bonus_count,
+            last_updated: registry.last_updated,
+        })
+    }
+
+    /// Get pending payouts for a recipient
+    /// This is a true read function that returns data directly
+    pub fn get_pending_payouts(ctx: Context<GetPendingPayouts>) -> Result<PayoutRegistry> {
+        let registry = &ctx.accounts.payout_registry;
+        
+        // Return the registry data directly
+        Ok(PayoutRegistry {
+            recipient: registry.recipient,
+            total_pending: registry.total_pending,
+            total_claimed: registry.total_claimed,
+            payout_count: registry.payout_count,
+            last_updated: registry.last_updated,
+            claimed_count: registry.claimed_count,
+            season_id: registry.season_id,
+            season_pending: registry.season_pending,
+        })
+    }
+
+    /// Get global payout statistics
+    /// This is a true read function that returns data directly
+    pub fn get_global_payout_stats(ctx: Context<GetGlobalPayoutStats>) -> Result<GlobalPayoutRegistry> {
+        let registry = &ctx.accounts.global_payout_registry;
+        
+        // Return the global registry data directly
+        Ok(GlobalPayoutRegistry {
+            total_pending: registry.total_pending,
+            total_claimed: registry.total_claimed,
+            total_payout_count: registry.total_payout_count,
+            total_recipient_count: registry.total_recipient_count,
+            last_updated: registry.last_updated,
+        })
+    }
+
+    /// Get global referral statistics
+    /// This is a true read function that returns data directly
+    pub fn get_global_referral_stats(ctx: Context<GetGlobalReferralStats>) -> Result<GlobalReferralRegistry> {
+        let registry = &ctx.accounts.global_referral_registry;
+        
+        // Return the global registry data directly
+        Ok(GlobalReferralRegistry {
+            total_pending: registry.total_pending,
+            total_claimed: registry.total_claimed,
+            total_bonus_count: registry.total_bonus_count,
+            total_referrer_count: registry.total_referrer_count,
+            last_updated: registry.last_updated,
+        })
+    }
+}
+
+/// Extra config seed for a vault namespace
+/// Vault 0 contributes no bytes, so existing `[b"config", mint]` deployments keep their address
+pub fn vault_id_seed(vault_id: u64) -> Vec<u8> {
+    if vault_id == 0 {
+        Vec::new()
+    } else {
+        vault_id.to_le_bytes().to_vec()
+    }
+}
+
+/// Extra seed for per-mint accounts
+/// The primary mint contributes no bytes, so its accounts keep their original addresses;
+/// SOL payouts use SOL_DENOMINATION here like any other accepted mint
+pub fn mint_seed(config: &Config, mint: &Pubkey) -> Vec<u8> {
+    if *mint == config.mint {
+        Vec::new()
+    } else {
+        mint.to_bytes().to_vec()
+    }
+}
+
+/// Pending referral bonuses backed by the given mint's vault
+/// Referral bonuses are only paid in the primary mint
+fn referral_pending_in(
+    config: &Config,
+    mint: &Pubkey,
+    global_referral_registry: &GlobalReferralRegistry,
+) -> u64 {
+    if *mint == config.mint {
+        global_referral_registry.total_pending
+    } else {
+        0
+    }
+}
+
+/// Lamports in the SOL vault above its rent-exempt minimum
+/// Only this excess can back payouts without the pool being reaped
+fn sol_vault_balance(sol_vault: &AccountInfo) -> Result<u64> {
+    let rent_exempt = Rent::get()?.minimum_balance(0);
+    Ok(sol_vault.lamports().saturating_sub(rent_exempt))
+}
+
+/// Moves lamports out of the system-owned SOL vault PDA
+fn transfer_sol_from_vault<'info>(
+    system_program: AccountInfo<'info>,
+    sol_vault: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    config_key: Pubkey,
+    sol_vault_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    let seeds: &[&[u8]] = &[
+        b"sol_vault",
+        config_key.as_ref(),
+        &[sol_vault_bump],
+    ];
+    let signer = &[seeds];
+    let cpi_ctx = CpiContext::new_with_signer(
+        system_program,
+        system_program::Transfer { from: sol_vault, to },
+        signer,
+    );
+    system_program::transfer(cpi_ctx, amount)
+}
+
+/// Fails with Overcommitted when pending payouts, pending referral bonuses and
+/// held entry fees exceed what the vault balance can back after the configured reserve
+fn require_solvent(
+    config: &Config,
+    vault_balance: u64,
+    payout_pending: u64,
+    referral_pending: u64,
+    entry_fees: u64,
+) -> Result<()> {
+    let obligations = payout_pending
+        .checked_add(referral_pending)
+        .and_then(|total| total.checked_add(entry_fees))
+        .ok_or(VaultError::Overflow)?;
+    require!(
+        obligations <= config.obligation_capacity(vault_balance),
+        VaultError::Overcommitted
+    );
+    Ok(())
+}
+
+/// Entry fees held for a mint's unsettled races (0 before the mint's first entrant)
+fn entry_fees_held(registry_info: &AccountInfo) -> Result<u64> {
+    if registry_info.data_is_empty() {
+        return Ok(0);
+    }
+    let registry = GlobalEntryFeeRegistry::try_deserialize(&mut &registry_info.try_borrow_data()?[..])?;
+    Ok(registry.total_held)
+}
+
+/// Applies a race's change in held entry fees to its mint's global entry fee
+/// registry and returns the mint's new total
+fn sync_entry_fees(registry_info: &AccountInfo, held_before: u64, held_after: u64, now: i64) -> Result<u64> {
+    if held_before == held_after {
+        return entry_fees_held(registry_info);
+    }
+    // Only races with entrants hold fees, and enter_race creates the registry
+    let mut registry = GlobalEntryFeeRegistry::try_deserialize(&mut &registry_info.try_borrow_data()?[..])?;
+    registry.total_held = registry.total_held
+        .checked_add(held_after)
+        .and_then(|total| total.checked_sub(held_before))
+        .ok_or(VaultError::Overflow)?;
+    registry.last_updated = now;
+    registry.try_serialize(&mut &mut registry_info.try_borrow_mut_data()?[..])?;
+    Ok(registry.total_held)
+}
+
+/// Emits the race's current lifecycle state for off-chain indexing
+fn emit_race_status(race: &Race, timestamp: i64) {
+    emit!(RaceStatusChangedEvent {
+        race_id_hash: race.race_id_hash,
+        status: race.status,
+        settled_total: race.settled_total,
+        finisher_count: race.finisher_count,
+        timestamp,
+    });
+}
+
+/// Counts registered payouts towards the active season, if its window is open
+/// The season account is required while a season is active
+fn attribute_registration(
+    config: &Config,
+    season: &mut Option<Box<Account<Season>>>,
+    mint: &Pubkey,
+    amount: u64,
+    count: u32,
+    now: i64,
+) -> Result<()> {
+    if config.season_id == 0 {
+        return Ok(());
+    }
+    let season = season.as_mut().ok_or(VaultError::SeasonAccountMissing)?;
+    season.record_registration(mint, amount, count, now)
+}
+
+/// Returns the season payouts registered at `now` count towards; 0 = none
+/// The season account is required while a season is active
+fn registration_season(config: &Config, season: &Option<Box<Account<Season>>>, now: i64) -> Result<u64> {
+    if config.season_id == 0 {
+        return Ok(0);
+    }
+    let season = season.as_ref().ok_or(VaultError::SeasonAccountMissing)?;
+    Ok(if season.is_registering(now) { season.season_id } else { 0 })
+}
+
+/// Counts claimed payouts registered in the active season towards it, if any
+fn attribute_claim(
+    config: &Config,
+    season: &mut Option<Box<Account<Season>>>,
+    mint: &Pubkey,
+    amount: u64,
+) -> Result<()> {
+    if config.season_id == 0 {
+        return Ok(());
+    }
+    let season = season.as_mut().ok_or(VaultError::SeasonAccountMissing)?;
+    season.record_claim(mint, amount)
+}
+
+/// Ranks the racer's season points on the active season's leaderboard, if any
+fn update_leaderboard(
+    season_id: u64,
+    leaderboard: &Option<AccountLoader<Leaderboard>>,
+    racer: Pubkey,
+    season_points: u64,
+) -> Result<()> {
+    if season_id == 0 {
+        return Ok(());
+    }
+    let leaderboard = leaderboard.as_ref().ok_or(VaultError::SeasonAccountMissing)?;
+    leaderboard.load_mut()?.record(racer, season_points);
+    Ok(())
+}
+
+/// Token amount, in base units of a mint with `decimals`, worth `points` at `points_rate`
+fn points_amount(points: u64, points_rate: u64, decimals: u8) -> Result<u64> {
+    let amount = (points as u128)
+        .checked_mul(points_rate as u128)
+        .and_then(|scaled| scaled.checked_mul(10u128.checked_pow(decimals as u32)?))
+        .ok_or(VaultError::Overflow)?
+        / 10u128.pow(POINTS_RATE_DECIMALS as u32);
+    Ok(u64::try_from(amount).map_err(|_| VaultError::Overflow)?)
+}
+
+/// Amount to register for a payout worth `points`
+/// With `amounts_from_points` on, the amount comes from the points rate and a
+/// client-supplied amount must be 0 or match it; otherwise the client amount is used
+fn payout_amount(config: &Config, points: u64, amount: u64, decimals: u8) -> Result<u64> {
+    if !config.amounts_from_points {
+        return Ok(amount);
+    }
+    let derived = config.points_to_amount(points, decimals)?;
+    require!(amount == 0 || amount == derived, VaultError::PointsAmountMismatch);
+    Ok(derived)
+}
+
+/// Fails unless the receipt's amount still sits in the recipient's pending total
+fn require_unsettled_payout(receipt: &PayoutReceipt, registry: &PayoutRegistry) -> Result<()> {
+    require!(receipt.status != PayoutStatus::Cancelled, VaultError::PayoutCancelled);
+    require!(receipt.status != PayoutStatus::Expired, VaultError::PayoutExpired);
+    require!(receipt.sequence >= registry.claimed_count, VaultError::PayoutAlreadyClaimed);
+    Ok(())
+}
+
+/// Replaces an unsettled payout's points and amount in its registries, race, season
+/// and points ledgers; the race and season stay within their budgets
+/// Returns the payout's old points and amount
+#[allow(clippy::too_many_arguments)]
+fn amend_registered_payout(
+    config: &Config,
+    mint: &Pubkey,
+    receipt: &mut PayoutReceipt,
+    registry: &mut PayoutRegistry,
+    global_registry: &mut GlobalPayoutRegistry,
+    race: &mut Option<Box<Account<Race>>>,
+    season: &mut Option<Box<Account<Season>>>,
+    racer_stats: &mut Option<Box<Account<RacerStats>>>,
+    season_stats: &mut Option<Box<Account<RacerStats>>>,
+    leaderboard: &Option<AccountLoader<Leaderboard>>,
+    global_entry_fee_registry: &AccountInfo,
+    new_points: u64,
+    new_amount: u64,
+    now: i64,
+) -> Result<(u64, u64)> {
+    require_unsettled_payout(receipt, registry)?;
+    require!(race.is_some() == receipt.has_race, VaultError::RaceAccountMismatch);
+
+    let old_points = receipt.points;
+    let old_amount = receipt.amount;
+    receipt.points = new_points;
+    receipt.amount = new_amount;
+    receipt.status = PayoutStatus::Amended;
+
+    // Replace the receipt's contribution to the registries
+    registry.total_pending = registry.total_pending
+        .checked_sub(old_amount)
+        .and_then(|pending| pending.checked_add(new_amount))
+        .ok_or(VaultError::Overflow)?;
+    registry.amend_season(receipt.season_id, old_amount, new_amount)?;
+    registry.last_updated = now;
+    global_registry.total_pending = global_registry.total_pending
+        .checked_sub(old_amount)
+        .and_then(|pending| pending.checked_add(new_amount))
+        .ok_or(VaultError::Overflow)?;
+    global_registry.last_updated = now;
+
+    // The race total follows the amendment and stays within the budget
+    if let Some(race) = race.as_mut() {
+        let fees_held = race.entry_fees_held();
+        race.settled_total = race.settled_total.checked_sub(old_amount).ok_or(VaultError::Overflow)?;
+        race.record_payouts(new_amount, 0)?;
+        sync_entry_fees(global_entry_fee_registry, fees_held, race.entry_fees_held(), now)?;
+    }
+
+    // The season total follows the amendment and stays within the budget
+    if receipt.season_id != 0 {
+        let season = season.as_mut().ok_or(VaultError::SeasonAccountMissing)?;
+        season.amend_registration(mint, old_amount, new_amount)?;
+    }
+
+    // Payouts registered against a race were recorded in the points ledgers
+    if receipt.has_race {
+        let racer_stats = racer_stats.as_mut().ok_or(VaultError::StatsAccountMissing)?;
+        racer_stats.amend_points(old_points, new_points)?;
+        if receipt.season_id != 0 {
+            let season_stats = season_stats.as_mut().ok_or(VaultError::StatsAccountMissing)?;
+            season_stats.amend_points(old_points, new_points)?;
+            if receipt.season_id == config.season_id {
+                update_leaderboard(receipt.season_id, leaderboard, receipt.recipient, season_stats.total_points)?;
+            }
+        }
+    }
+
+    Ok((old_points, old_amount))
+}
+
+/// Adds a registered payout to the recipient's registry and the global registry
+/// Returns the payout's sequence number within the recipient's registry
+fn credit_payout(
+    registry: &mut PayoutRegistry,
+    global_registry: &mut GlobalPayoutRegistry,
+    recipient: Pubkey,
+    amount: u64,
+    season_id: u64,
+    now: i64,
+) -> Result<u32> {
+    let (sequence, is_new_recipient) = credit_recipient(registry, recipient, amount, season_id, now)?;
+    global_registry.record_payouts(amount, 1, is_new_recipient as u32, now)?;
+    Ok(sequence)
+}
+
+/// Adds a registered payout to the recipient's registry only; batches add their
+/// total to the global registry once
+/// Returns the payout's sequence number and whether it is the recipient's first payout
+fn credit_recipient(
+    registry: &mut PayoutRegistry,
+    recipient: Pubkey,
+    amount: u64,
+    season_id: u64,
+    now: i64,
+) -> Result<(u32, bool)> {
+    let sequence = registry.payout_count;
+
+    // Track if this is a new recipient for global stats
+    let is_new_recipient = registry.recipient == Pubkey::default();
+    
+    // Initialize registry if this is the first payout
+    if is_new_recipient {
+        registry.recipient = recipient;
+    }
+    
+    registry.total_pending = registry.total_pending.checked_add(amount).ok_or(VaultError::Overflow)?;
+    registry.credit_season(season_id, amount)?;
+    registry.payout_count += 1;
+    registry.last_updated = now;
+
+    Ok((sequence, is_new_recipient))
+}
+
+/// Creates the receipt for one batch entry and credits the recipient's registries
+/// and stats. `accounts` is the entry's (payout_receipt, payout_registry,
+/// racer_stats, season_stats) group; registries and stats are created on first use.
+/// Returns the recipient's updated season points (0 outside a season) and whether
+/// this is the recipient's first payout; the caller credits the global registry
+#[allow(clippy::too_many_arguments)]
+fn register_batch_entry<'info>(
+    config: &Config,
+    config_key: Pubkey,
+    mint_seed: &[u8],
+    race_id_hash: [u8; 32],
+    entry: &PayoutEntry,
+    place: u32,
+    accounts: &[AccountInfo<'info>],
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    season_id: u64,
+    now: i64,
+) -> Result<(u64, bool)> {
+    let (receipt_info, registry_info) = (&accounts[0], &accounts[1]);
+
+    // One receipt per (race_id_hash, recipient). Prevents replays.
+    let (receipt_key, receipt_bump) = Pubkey::find_program_address(
+        &[b"receipt", config_key.as_ref(), &race_id_hash, entry.recipient.as_ref(), mint_seed],
+        &crate::ID,
+    );
+    require!(receipt_info.key() == receipt_key, VaultError::InvalidRemainingAccounts);
+    require!(receipt_info.data_is_empty(), VaultError::PayoutAlreadyExists);
+    create_pda_account(
+        payer,
+        receipt_info,
+        system_program,
+        8 + PayoutReceipt::SIZE,
+        &[b"receipt", config_key.as_ref(), &race_id_hash, entry.recipient.as_ref(), mint_seed, &[receipt_bump]],
+    )?;
+
+    // Payout registry (created on the recipient's first payout)
+    let (registry_key, registry_bump) = Pubkey::find_program_address(
+        &[b"payout_registry", config_key.as_ref(), entry.recipient.as_ref(), mint_seed],
+        &crate::ID,
+    );
+    require!(registry_info.key() == registry_key, VaultError::InvalidRemainingAccounts);
+    let mut registry = if registry_info.data_is_empty() {
+        create_pda_account(
+            payer,
+            registry_info,
+            system_program,
+            8 + PayoutRegistry::SIZE,
+            &[b"payout_registry", config_key.as_ref(), entry.recipient.as_ref(), mint_seed, &[registry_bump]],
+        )?;
+        PayoutRegistry {
+            recipient: Pubkey::default(),
+            total_pending: 0,
+            total_claimed: 0,
+            payout_count: 0,
+            last_updated: 0,
+            claimed_count: 0,
+            season_id: 0,
+            season_pending: 0,
+        }
+    } else {
+        PayoutRegistry::try_deserialize(&mut &registry_info.try_borrow_data()?[..])?
+    };
+    let (sequence, is_new_recipient) = credit_recipient(&mut registry, entry.recipient, entry.amount, season_id, now)?;
+    registry.try_serialize(&mut &mut registry_info.try_borrow_mut_data()?[..])?;
+
+    let receipt = PayoutReceipt {
+        race_id_hash,
+        recipient: entry.recipient,
+        points: entry.points,
+        amount: entry.amount,
+        timestamp: now,
+        status: PayoutStatus::Active,
+        sequence,
+        expires_at: config.payout_expiry(now)?,
+        has_race: true,
+        season_id,
+    };
+    receipt.try_serialize(&mut &mut receipt_info.try_borrow_mut_data()?[..])?;
+
+    // Lifetime points ledger, and the season ledger if the payout counts towards one
+    record_stats(config_key, &accounts[2], entry.recipient, 0, entry.points, place, payer, system_program, now)?;
+    if season_id == 0 {
+        return Ok((0, is_new_recipient));
+    }
+    let season_points = record_stats(config_key, &accounts[3], entry.recipient, season_id, entry.points, place, payer, system_program, now)?;
+    Ok((season_points, is_new_recipient))
+}
+
+/// Adds a race result to the racer's lifetime (`season_id` 0) or season points
+/// ledger, creating it on first use. Returns the ledger's updated points
+#[allow(clippy::too_many_arguments)]
+fn record_stats<'info>(
+    config_key: Pubkey,
+    stats_info: &AccountInfo<'info>,
+    racer: Pubkey,
+    season_id: u64,
+    points: u64,
+    place: u32,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    now: i64,
+) -> Result<u64> {
+    let season_seed = season_id.to_le_bytes();
+    let seeds = if season_id == 0 {
+        vec![b"racer_stats".as_ref(), config_key.as_ref(), racer.as_ref()]
+    } else {
+        vec![b"season_stats".as_ref(), config_key.as_ref(), racer.as_ref(), season_seed.as_ref()]
+    };
+    let (stats_key, stats_bump) = Pubkey::find_program_address(&seeds, &crate::ID);
+    require!(stats_info.key() == stats_key, VaultError::InvalidRemainingAccounts);
+    let mut stats = if stats_info.data_is_empty() {
+        let bump_seed = [stats_bump];
+        let mut signer_seeds = seeds.clone();
+        signer_seeds.push(&bump_seed);
+        create_pda_account(payer, stats_info, system_program, 8 + RacerStats::SIZE, &signer_seeds)?;
+        RacerStats {
+            racer: Pubkey::default(),
+            season_id,
+            total_points: 0,
+            race_count: 0,
+            best_finish: 0,
+            last_race_at: 0,
+        }
+    } else {
+        RacerStats::try_deserialize(&mut &stats_info.try_borrow_data()?[..])?
+    };
+    stats.record_race(racer, points, place, now)?;
+    stats.try_serialize(&mut &mut stats_info.try_borrow_mut_data()?[..])?;
+    Ok(stats.total_points)
+}
+
+/// Moves `amount` tokens from the depositor into the vault and credits what arrived,
+/// net of any Token-2022 transfer fee, to the depositor's ledger
+fn deposit_tokens<'info>(
+    ctx: Context<'_, '_, '_, 'info, Deposit<'info>>,
+    amount: u64,
+    memo: Option<String>,
+) -> Result<()> {
+    require!(amount > 0, VaultError::ZeroAmount);
+    if let Some(memo) = &memo {
+        require!(memo.len() <= MAX_MEMO_LEN, VaultError::MemoTooLong);
+    }
+
+    let balance_before = ctx.accounts.vault_token.amount;
+    transfer_checked_with_hook(
+        ctx.accounts.depositor_token.to_account_info(),
+        &ctx.accounts.mint,
+        ctx.accounts.vault_token.to_account_info(),
+        ctx.accounts.depositor.to_account_info(),
+        ctx.remaining_accounts,
+        amount,
+        &[],
+    )?;
+
+    // Credit what actually arrived, net of any Token-2022 transfer fee
+    ctx.accounts.vault_token.reload()?;
+    let received = ctx.accounts.vault_token.amount
+        .checked_sub(balance_before)
+        .ok_or(VaultError::Overflow)?;
+
+    // Update depositor ledger
+    let now = Clock::get()?.unix_timestamp;
+    let ledger = &mut ctx.accounts.depositor_ledger;
+    if ledger.depositor == Pubkey::default() {
+        ledger.depositor = ctx.accounts.depositor.key();
+        ledger.first_deposit_at = now;
+    }
+    ledger.total_deposited = ledger.total_deposited.checked_add(received).ok_or(VaultError::Overflow)?;
+    ledger.deposit_count += 1;
+    ledger.last_deposit_at = now;
+
+    // Emit for off-chain indexing
+    emit!(DepositEvent {
+        depositor: ledger.depositor,
+        amount: received,
+        transfer_fee: amount.saturating_sub(received),
+        vault_balance: ctx.accounts.vault_token.amount,
+        total_deposited: ledger.total_deposited,
+        memo,
+        mint: ctx.accounts.mint.key(),
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Moves `amount` lamports from the depositor into the SOL vault and credits the
+/// depositor's SOL ledger
+fn deposit_lamports(ctx: Context<DepositSol>, amount: u64, memo: Option<String>) -> Result<()> {
+    require!(amount > 0, VaultError::ZeroAmount);
+    if let Some(memo) = &memo {
+        require!(memo.len() <= MAX_MEMO_LEN, VaultError::MemoTooLong);
+    }
+
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        system_program::Transfer {
+            from: ctx.accounts.depositor.to_account_info(),
+            to: ctx.accounts.sol_vault.to_account_info(),
+        },
+    );
+    system_program::transfer(cpi_ctx, amount)?;
+
+    // Update depositor ledger
+    let now = Clock::get()?.unix_timestamp;
+    let ledger = &mut ctx.accounts.depositor_ledger;
+    if ledger.depositor == Pubkey::default() {
+        ledger.depositor = ctx.accounts.depositor.key();
+        ledger.first_deposit_at = now;
+    }
+    ledger.total_deposited = ledger.total_deposited.checked_add(amount).ok_or(VaultError::Overflow)?;
+    ledger.deposit_count += 1;
+    ledger.last_deposit_at = now;
+
+    // Emit for off-chain indexing
+    emit!(DepositEvent {
+        depositor: ledger.depositor,
+        amount,
+        transfer_fee: 0,
+        vault_balance: ctx.accounts.sol_vault.lamports(),
+        total_deposited: ledger.total_deposited,
+        memo,
+        mint: SOL_DENOMINATION,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Moves tokens with transfer_checked under the mint's token program (SPL Token or
+/// Token-2022), forwarding the extra accounts a transfer-hook mint needs
+fn transfer_checked_with_hook<'info>(
+    from: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    hook_accounts: &[AccountInfo<'info>],
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mint_info = mint.to_account_info();
+    invoke_transfer_checked(
+        mint_info.owner,
+        from,
+        mint_info.clone(),
+        to,
+        authority,
+        hook_accounts,
+        amount,
+        mint.decimals,
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Fee a Token-2022 transfer-fee mint withholds from `amount` this epoch (0 otherwise)
+fn transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    if mint_info.owner != &spl_token_2022::ID {
+        return Ok(0);
+    }
+    let data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let fee = match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(fee_config) => fee_config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(VaultError::Overflow)?,
+        Err(_) => 0,
+    };
+    Ok(fee)
+}
+
+/// Whether a Token-2022 account holds withheld transfer fees
+fn has_withheld_fees(token_account: &InterfaceAccount<TokenAccount>) -> Result<bool> {
+    let account_info = token_account.to_account_info();
+    if account_info.owner != &spl_token_2022::ID {
+        return Ok(false);
+    }
+    let data = account_info.try_borrow_data()?;
+    let account_state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    let withheld = account_state
+        .get_extension::<TransferFeeAmount>()
+        .map(|fee_amount| u64::from(fee_amount.withheld_amount))
+        .unwrap_or(0);
+    Ok(withheld > 0)
+}
+
+/// Bytes the voucher signer signs for claim_with_voucher:
+/// config || mint || race_id_hash || recipient || points_le || amount_le || expiry_le || nonce_le
+#[allow(clippy::too_many_arguments)]
+pub fn voucher_message(
+    config: &Pubkey,
+    mint: &Pubkey,
+    race_id_hash: &[u8; 32],
+    recipient: &Pubkey,
+    points: u64,
+    amount: u64,
+    expiry: i64,
+    nonce: u64,
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(32 * 4 + 8 * 4);
+    message.extend_from_slice(config.as_ref());
+    message.extend_from_slice(mint.as_ref());
+    message.extend_from_slice(race_id_hash);
+    message.extend_from_slice(recipient.as_ref());
+    message.extend_from_slice(&points.to_le_bytes());
+    message.extend_from_slice(&amount.to_le_bytes());
+    message.extend_from_slice(&expiry.to_le_bytes());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message
+}
+
+/// Fails unless the previous instruction is an ed25519 precompile check of a single
+/// signature by `signer` over exactly `message`, with all data inside that instruction
+fn verify_ed25519_signature(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let current_index = load_current_index_checked(instructions)?;
+    require!(current_index > 0, VaultError::MissingVoucherSignature);
+    let ed25519_ix = load_instruction_at_checked(current_index as usize - 1, instructions)?;
+    require!(ed25519_ix.program_id == ed25519_program::ID, VaultError::MissingVoucherSignature);
+
+    // Header: num_signatures, padding, then one 14-byte offsets record
+    let data = &ed25519_ix.data;
+    require!(data.len() >= 16 && data[0] == 1, VaultError::InvalidVoucherSignature);
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+
+    // u16::MAX points each field at the precompile instruction itself
+    for instruction_index_at in [4, 8, 14] {
+        require!(read_u16(instruction_index_at) == u16::MAX, VaultError::InvalidVoucherSignature);
+    }
+    let public_key_offset = read_u16(6) as usize;
+    let message_offset = read_u16(10) as usize;
+    let message_size = read_u16(12) as usize;
+
+    let public_key = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(VaultError::InvalidVoucherSignature)?;
+    let signed_message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(VaultError::InvalidVoucherSignature)?;
+    require!(
+        public_key == signer.as_ref() && signed_message == message,
+        VaultError::InvalidVoucherSignature
+    );
+    Ok(())
+}
+
+/// Verifies a sorted-pair merkle proof for `leaf` against `root`
+fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        let (left, right) = if node <= *sibling { (node, *sibling) } else { (*sibling, node) };
+        hashv(&[&[1u8], &left, &right]).to_bytes()
+    });
+    computed == root
+}
+
+/// Grows a program-owned account to `new_len`, topping up rent from `payer`;
+/// the new trailing bytes are zero-filled
+fn grow_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let required_lamports = Rent::get()?.minimum_balance(new_len);
+    let top_up = required_lamports.saturating_sub(account.lamports());
+    if top_up > 0 {
+        let cpi_ctx = CpiContext::new(
+            system_program.clone(),
+            system_program::Transfer {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+        );
+        system_program::transfer(cpi_ctx, top_up)?;
+    }
+    account.resize(new_len)?;
+    Ok(())
+}
+
+/// Creates a program-owned PDA account, also when someone pre-funded its address
+fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    new_account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let required_lamports = Rent::get()?.minimum_balance(space);
+    let current_lamports = new_account.lamports();
+    let signer = &[signer_seeds];
+
+    if current_lamports == 0 {
+        let cpi_ctx = CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::CreateAccount {
+                from: payer.clone(),
+                to: new_account.clone(),
+            },
+            signer,
+        );
+        return system_program::create_account(cpi_ctx, required_lamports, space as u64, &crate::ID);
+    }
+
+    let top_up = required_lamports.saturating_sub(current_lamports);
+    if top_up > 0 {
+        let cpi_ctx = CpiContext::new(
+            system_program.clone(),
+            system_program::Transfer {
+                from: payer.clone(),
+                to: new_account.clone(),
+            },
+        );
+        system_program::transfer(cpi_ctx, top_up)?;
+    }
+    let cpi_ctx = CpiContext::new_with_signer(
+        system_program.clone(),
+        system_program::Allocate {
+            account_to_allocate: new_account.clone(),
+        },
+        signer,
+    );
+    system_program::allocate(cpi_ctx, space as u64)?;
+    let cpi_ctx = CpiContext::new_with_signer(
+        system_program.clone(),
+        system_program::Assign {
+            account_to_assign: new_account.clone(),
+        },
+        signer,
+    );
+    system_program::assign(cpi_ctx, &crate::ID)
+}
+
+#[derive(Accounts)]
+#[instruction(vault_id: u64)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Config::SIZE,
+        seeds = [b"config", mint.key().as_ref(), vault_id_seed(vault_id).as_slice()],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump
+        // no init: it's just the signer PDA, no lamports
+    )]
+    /// CHECK: PDA signer, no data
+    pub vault_signer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program-owned vault ATA (created if missing)
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// Global payout registry (tracks all payouts across all recipients)
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GlobalPayoutRegistry::SIZE,
+        seeds = [b"global_payout_registry", config.key().as_ref()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    /// Global referral registry (tracks all referral bonuses across all referrers)
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GlobalReferralRegistry::SIZE,
+        seeds = [b"global_referral_registry", config.key().as_ref()],
+        bump
+    )]
+    pub global_referral_registry: Account<'info, GlobalReferralRegistry>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Migrate<'info> {
+    /// Config in its pre-migration layout (authority checked in the handler)
+    /// Only vault 0 predates vault namespaces, so the legacy seeds are used
+    #[account(
+        mut,
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: may not deserialize as the current Config until resized
+    pub config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Global referral registry (created for deployments that predate it)
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GlobalReferralRegistry::SIZE,
+        seeds = [b"global_referral_registry", config.key().as_ref()],
+        bump
+    )]
+    pub global_referral_registry: Account<'info, GlobalReferralRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePayouts<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = authority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Recipient whose payout accounts are migrated
+    /// CHECK: only used as a seed
+    pub recipient: UncheckedAccount<'info>,
+
+    /// Primary-mint payout registry in its legacy layout
+    #[account(
+        mut,
+        seeds = [b"payout_registry", config.key().as_ref(), recipient.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: may not deserialize as the current PayoutRegistry until resized
+    pub payout_registry: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateReferralBonuses<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = authority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Referrer whose bonus records are migrated
+    /// CHECK: only used as a seed
+    pub referrer: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"referrer_registry", config.key().as_ref(), referrer.key().as_ref()],
+        bump
+    )]
+    pub referrer_registry: Account<'info, ReferrerRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    /// PDA signer (authority of the vault token)
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = depositor,
+        associated_token::token_program = token_program
+    )]
+    pub depositor_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// Depositor ledger (tracks cumulative deposits for this depositor)
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = 8 + DepositorLedger::SIZE,
+        seeds = [b"depositor_ledger", config.key().as_ref(), depositor.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub depositor_ledger: Account<'info, DepositorLedger>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSol<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&SOL_DENOMINATION) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Lamport pool backing SOL payouts
+    #[account(
+        mut,
+        seeds = [b"sol_vault", config.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// Depositor ledger (tracks cumulative SOL deposits for this depositor)
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = 8 + DepositorLedger::SIZE,
+        seeds = [b"depositor_ledger", config.key().as_ref(), depositor.key().as_ref(), mint_seed(&config, &SOL_DENOMINATION).as_slice()],
+        bump
+    )]
+    pub depositor_ledger: Account<'info, DepositorLedger>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(table_id: u64)]
+pub struct CreatePrizeTable<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = authority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PrizeTable::SIZE,
+        seeds = [b"prize_table", config.key().as_ref(), &table_id.to_le_bytes()],
+        bump
+    )]
+    pub prize_table: Account<'info, PrizeTable>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(race_id: String, race_id_hash: [u8; 32])]
+pub struct CreateRace<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted,
+        constraint = config.has_role(Role::Operator, &operator.key()) @ VaultError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Payout operator (only the operator can manage races)
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    /// Mint the prize budget is denominated in, or SOL_DENOMINATION
+    /// CHECK: must be accepted by config (constraint above)
+    pub mint: UncheckedAccount<'info>,
+
+    /// Prize table settle_race will apply to this race (optional)
+    #[account(
+        seeds = [b"prize_table", config.key().as_ref(), &prize_table.table_id.to_le_bytes()],
+        bump
+    )]
+    pub prize_table: Option<Account<'info, PrizeTable>>,
+
+    /// One race per race_id_hash
+    #[account(
+        init,
+        payer = operator,
+        space = 8 + Race::SIZE,
+        seeds = [b"race", config.key().as_ref(), &race_id_hash],
+        bump
+    )]
+    pub race: Account<'info, Race>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(race_id_hash: [u8; 32])]
+pub struct EnterRace<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"race", config.key().as_ref(), &race_id_hash],
+        bump,
+        constraint = race.status == RaceStatus::Open @ VaultError::RaceNotOpen,
+        constraint = race.mint == mint.key() @ VaultError::RaceMintMismatch
+    )]
+    pub race: Box<Account<'info, Race>>,
+
+    /// One ticket per racer and race
+    #[account(
+        init,
+        payer = racer,
+        space = 8 + EntryTicket::SIZE,
+        seeds = [b"entry_ticket", config.key().as_ref(), &race_id_hash, racer.key().as_ref()],
+        bump
+    )]
+    pub entry_ticket: Account<'info, EntryTicket>,
+
+    /// Entry fees held for this mint's unsettled races (created on the first entrant)
+    #[account(
+        init_if_needed,
+        payer = racer,
+        space = 8 + GlobalEntryFeeRegistry::SIZE,
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub global_entry_fee_registry: Box<Account<'info, GlobalEntryFeeRegistry>>,
+
+    /// PDA signer owning the vault ATA
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub racer: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = racer,
+        associated_token::token_program = token_program
+    )]
+    pub racer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+#[instruction(race_id_hash: [u8; 32])]
+pub struct RefundEntry<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"race", config.key().as_ref(), &race_id_hash],
+        bump,
+        constraint = race.status == RaceStatus::Cancelled @ VaultError::RaceNotCancelled,
+        constraint = race.mint == mint.key() @ VaultError::RaceMintMismatch
+    )]
+    pub race: Box<Account<'info, Race>>,
+
+    /// Closed once refunded; its rent returns to the racer
+    #[account(
+        mut,
+        seeds = [b"entry_ticket", config.key().as_ref(), &race_id_hash, racer.key().as_ref()],
+        bump,
+        close = racer
+    )]
+    pub entry_ticket: Account<'info, EntryTicket>,
+
+    /// Entry fees held for this mint's unsettled races
+    #[account(
+        mut,
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub global_entry_fee_registry: Box<Account<'info, GlobalEntryFeeRegistry>>,
+
+    /// PDA signer owning the vault ATA
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Racer named in the ticket
+    /// CHECK: bound by the ticket seeds
+    #[account(mut)]
+    pub racer: UncheckedAccount<'info>,
+
+    /// Racer ATA (auto-created if needed)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = racer,
+        associated_token::token_program = token_program
+    )]
+    pub racer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Payer for transaction fees (can be anyone)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+#[instruction(race_id_hash: [u8; 32])]
+pub struct UpdateRace<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.has_role(Role::Operator, &operator.key()) @ VaultError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Payout operator (only the operator can manage races)
+    pub operator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"race", config.key().as_ref(), &race_id_hash],
+        bump
+    )]
+    pub race: Account<'info, Race>,
+
+    /// Entry fees held for unsettled races in this mint (empty until its first entrant)
+    #[account(
+        mut,
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &race.mint).as_slice()],
+        bump
+    )]
+    /// CHECK: read and written through entry_fees_held / sync_entry_fees
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(season_id: u64)]
+pub struct OpenSeason<'info> {
+    #[account(
+        mut,
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = authority,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Mint the season budget is denominated in, or SOL_DENOMINATION
+    /// CHECK: must be accepted by config (constraint above)
+    pub mint: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Season::SIZE,
+        seeds = [b"season", config.key().as_ref(), &season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Account<'info, Season>,
+
+    /// Top racers of the season by season points
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Leaderboard::SIZE,
+        seeds = [b"leaderboard", config.key().as_ref(), &season_id.to_le_bytes()],
+        bump
+    )]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSeason<'info> {
+    #[account(
+        mut,
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = authority
+    )]
+    pub config: Account<'info, Config>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Account<'info, Season>,
+}
+
+#[derive(Accounts)]
+#[instruction(race_id: String, race_id_hash: [u8; 32])]
+pub struct RegisterPayout<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted,
+        constraint = config.has_role(Role::Operator, &operator.key()) @ VaultError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Payout operator (only the operator can register payouts)
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Recipient wallet to receive payout
+    /// CHECK: validated by ATA derivation below
+    pub recipient: UncheckedAccount<'info>,
+
+    /// One receipt per (race_id_hash, recipient). Prevents replays.
+    #[account(
+        init,
+        payer = operator,
+        space = 8 + PayoutReceipt::SIZE,
+        seeds = [
+            b"receipt", 
+            config.key().as_ref(), 
+            &race_id_hash,
+            recipient.key().as_ref(),
+            mint_seed(&config, &mint.key()).as_slice()
+        ],
+        bump
+    )]
+    pub payout_receipt: Account<'info, PayoutReceipt>,
+
+    /// Race being settled; its payouts must stay within the prize budget
+    #[account(
+        mut,
+        seeds = [b"race", config.key().as_ref(), &race_id_hash],
+        bump,
+        constraint = race.status == RaceStatus::Running @ VaultError::RaceNotRunning,
+        constraint = race.prize_table == Pubkey::default() @ VaultError::RaceUsesPrizeTable,
+        constraint = race.mint == mint.key() @ VaultError::RaceMintMismatch
+    )]
+    pub race: Box<Account<'info, Race>>,
+
+    /// Must not exist: once a race's receipts are closed it cannot be registered again
+    #[account(
+        seeds = [b"race_tombstone", config.key().as_ref(), &race_id_hash],
+        bump,
+        constraint = race_tombstone.data_is_empty() @ VaultError::RaceReceiptsClosed
+    )]
+    /// CHECK: only checked for absence
+    pub race_tombstone: UncheckedAccount<'info>,
+
+    /// Payout registry (tracks total pending payouts for this recipient)
+    #[account(
+        init_if_needed,
+        payer = operator,
+        space = 8 + PayoutRegistry::SIZE,
+        seeds = [b"payout_registry", config.key().as_ref(), recipient.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub payout_registry: Account<'info, PayoutRegistry>,
+
+    /// Lifetime points ledger for the recipient
+    #[account(
+        init_if_needed,
+        payer = operator,
+        space = 8 + RacerStats::SIZE,
+        seeds = [b"racer_stats", config.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub racer_stats: Box<Account<'info, RacerStats>>,
+
+    /// Points ledger for the recipient in the active season (unused outside its window)
+    #[account(
+        mut,
+        seeds = [b"season_stats", config.key().as_ref(), recipient.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    /// CHECK: created and written through record_stats
+    pub season_stats: UncheckedAccount<'info>,
+
+    /// Global payout registry (tracks all payouts)
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    /// Active season the payouts count towards (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    /// Leaderboard of the active season (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"leaderboard", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub leaderboard: Option<AccountLoader<'info, Leaderboard>>,
+
+    /// Global referral registry (counts towards outstanding obligations)
+    #[account(
+        seeds = [b"global_referral_registry", config.key().as_ref()],
+        bump
+    )]
+    pub global_referral_registry: Account<'info, GlobalReferralRegistry>,
+
+    /// Entry fees held for unsettled races in this mint (empty until its first entrant)
+    #[account(
+        mut,
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    /// CHECK: read and written through entry_fees_held / sync_entry_fees
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
+
+    /// PDA signer owning the vault ATA
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
+
+    /// Vault balance backing the new obligation
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(race_id: String, race_id_hash: [u8; 32])]
+pub struct RegisterSolPayout<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&SOL_DENOMINATION) @ VaultError::MintNotAccepted,
+        constraint = config.has_role(Role::Operator, &operator.key()) @ VaultError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Payout operator (only the operator can register payouts)
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    /// Recipient wallet to receive payout
+    /// CHECK: only used as a seed and recorded on the receipt
+    pub recipient: UncheckedAccount<'info>,
+
+    /// One receipt per (race_id_hash, recipient). Prevents replays.
+    #[account(
+        init,
+        payer = operator,
+        space = 8 + PayoutReceipt::SIZE,
+        seeds = [
+            b"receipt",
+            config.key().as_ref(),
+            &race_id_hash,
+            recipient.key().as_ref(),
+            mint_seed(&config, &SOL_DENOMINATION).as_slice()
+        ],
+        bump
+    )]
+    pub payout_receipt: Account<'info, PayoutReceipt>,
+
+    /// Race being settled; its payouts must stay within the prize budget
+    #[account(
+        mut,
+        seeds = [b"race", config.key().as_ref(), &race_id_hash],
+        bump,
+        constraint = race.status == RaceStatus::Running @ VaultError::RaceNotRunning,
+        constraint = race.prize_table == Pubkey::default() @ VaultError::RaceUsesPrizeTable,
+        constraint = race.mint == SOL_DENOMINATION @ VaultError::RaceMintMismatch
+    )]
+    pub race: Box<Account<'info, Race>>,
+
+    /// Must not exist: once a race's receipts are closed it cannot be registered again
+    #[account(
+        seeds = [b"race_tombstone", config.key().as_ref(), &race_id_hash],
+        bump,
+        constraint = race_tombstone.data_is_empty() @ VaultError::RaceReceiptsClosed
+    )]
+    /// CHECK: only checked for absence
+    pub race_tombstone: UncheckedAccount<'info>,
+
+    /// Payout registry (tracks total pending SOL payouts for this recipient)
+    #[account(
+        init_if_needed,
+        payer = operator,
+        space = 8 + PayoutRegistry::SIZE,
+        seeds = [b"payout_registry", config.key().as_ref(), recipient.key().as_ref(), mint_seed(&config, &SOL_DENOMINATION).as_slice()],
+        bump
+    )]
+    pub payout_registry: Account<'info, PayoutRegistry>,
+
+    /// Lifetime points ledger for the recipient
+    #[account(
+        init_if_needed,
+        payer = operator,
+        space = 8 + RacerStats::SIZE,
+        seeds = [b"racer_stats", config.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub racer_stats: Box<Account<'info, RacerStats>>,
+
+    /// Points ledger for the recipient in the active season (unused outside its window)
+    #[account(
+        mut,
+        seeds = [b"season_stats", config.key().as_ref(), recipient.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    /// CHECK: created and written through record_stats
+    pub season_stats: UncheckedAccount<'info>,
+
+    /// Global payout registry for SOL
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &SOL_DENOMINATION).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    /// Active season the payouts count towards (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    /// Leaderboard of the active season (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"leaderboard", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub leaderboard: Option<AccountLoader<'info, Leaderboard>>,
+
+    /// Lamport pool backing the new obligation
+    #[account(
+        seeds = [b"sol_vault", config.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(race_id: String, race_id_hash: [u8; 32])]
+pub struct RegisterRacePayouts<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted,
+        constraint = config.has_role(Role::Operator, &operator.key()) @ VaultError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Payout operator (pays rent for receipts and new registries)
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Race being settled; its payouts must stay within the prize budget
+    #[account(
+        mut,
+        seeds = [b"race", config.key().as_ref(), &race_id_hash],
+        bump,
+        constraint = race.status == RaceStatus::Running @ VaultError::RaceNotRunning,
+        constraint = race.prize_table == Pubkey::default() @ VaultError::RaceUsesPrizeTable,
+        constraint = race.mint == mint.key() @ VaultError::RaceMintMismatch
+    )]
+    pub race: Box<Account<'info, Race>>,
+
+    /// Must not exist: once a race's receipts are closed it cannot be registered again
+    #[account(
+        seeds = [b"race_tombstone", config.key().as_ref(), &race_id_hash],
+        bump,
+        constraint = race_tombstone.data_is_empty() @ VaultError::RaceReceiptsClosed
+    )]
+    /// CHECK: only checked for absence
+    pub race_tombstone: UncheckedAccount<'info>,
+
+    /// Global payout registry (updated once per batch)
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    /// Active season the payouts count towards (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    /// Leaderboard of the active season (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"leaderboard", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub leaderboard: Option<AccountLoader<'info, Leaderboard>>,
+
+    /// Global referral registry (counts towards outstanding obligations)
+    #[account(
+        seeds = [b"global_referral_registry", config.key().as_ref()],
+        bump
+    )]
+    pub global_referral_registry: Account<'info, GlobalReferralRegistry>,
+
+    /// Entry fees held for unsettled races in this mint (empty until its first entrant)
+    #[account(
+        mut,
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    /// CHECK: read and written through entry_fees_held / sync_entry_fees
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
+
+    /// PDA signer owning the vault ATA
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
+
+    /// Vault balance backing the new obligations
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(race_id: String, race_id_hash: [u8; 32])]
+pub struct SettleRace<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted,
+        constraint = config.has_role(Role::Operator, &operator.key()) @ VaultError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Payout operator (pays rent for receipts and new registries)
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Race being settled; moves to Settled
+    #[account(
+        mut,
+        seeds = [b"race", config.key().as_ref(), &race_id_hash],
+        bump,
+        constraint = race.status == RaceStatus::Running @ VaultError::RaceNotRunning,
+        constraint = race.mint == mint.key() @ VaultError::RaceMintMismatch,
+        constraint = race.prize_table == prize_table.key() @ VaultError::PrizeTableMismatch
+    )]
+    pub race: Box<Account<'info, Race>>,
+
+    /// Prize table the race was created with
+    pub prize_table: Box<Account<'info, PrizeTable>>,
+
+    /// Must not exist: once a race's receipts are closed it cannot be registered again
+    #[account(
+        seeds = [b"race_tombstone", config.key().as_ref(), &race_id_hash],
+        bump,
+        constraint = race_tombstone.data_is_empty() @ VaultError::RaceReceiptsClosed
+    )]
+    /// CHECK: only checked for absence
+    pub race_tombstone: UncheckedAccount<'info>,
+
+    /// Global payout registry (updated once per batch)
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    /// Active season the payouts count towards (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    /// Leaderboard of the active season (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"leaderboard", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub leaderboard: Option<AccountLoader<'info, Leaderboard>>,
+
+    /// Global referral registry (counts towards outstanding obligations)
+    #[account(
+        seeds = [b"global_referral_registry", config.key().as_ref()],
+        bump
+    )]
+    pub global_referral_registry: Account<'info, GlobalReferralRegistry>,
+
+    /// Entry fees held for unsettled races in this mint (empty until its first entrant)
+    #[account(
+        mut,
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    /// CHECK: read and written through entry_fees_held / sync_entry_fees
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
+
+    /// PDA signer owning the vault ATA
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
+
+    /// Vault balance backing the new obligations
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(race_id: String, race_id_hash: [u8; 32], merkle_root: [u8; 32], total_amount: u64, leaf_count: u32)]
+pub struct PostRaceDistribution<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted,
+        constraint = config.has_role(Role::Operator, &operator.key()) @ VaultError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Payout operator (only the operator can post distributions)
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Race being settled; its payouts must stay within the prize budget
+    #[account(
+        mut,
+        seeds = [b"race", config.key().as_ref(), &race_id_hash],
+        bump,
+        constraint = race.status == RaceStatus::Running @ VaultError::RaceNotRunning,
+        constraint = race.prize_table == Pubkey::default() @ VaultError::RaceUsesPrizeTable,
+        constraint = race.mint == mint.key() @ VaultError::RaceMintMismatch
+    )]
+    pub race: Box<Account<'info, Race>>,
+
+    /// One distribution per race and mint
+    #[account(
+        init,
+        payer = operator,
+        space = 8 + RaceDistribution::space(leaf_count),
+        seeds = [b"race_distribution", config.key().as_ref(), &race_id_hash, mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub race_distribution: Box<Account<'info, RaceDistribution>>,
+
+    /// Global payout registry (tracks all payouts)
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    /// Active season the payouts count towards (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    /// Global referral registry (counts towards outstanding obligations)
+    #[account(
+        seeds = [b"global_referral_registry", config.key().as_ref()],
+        bump
+    )]
+    pub global_referral_registry: Account<'info, GlobalReferralRegistry>,
+
+    /// Entry fees held for unsettled races in this mint (empty until its first entrant)
+    #[account(
+        mut,
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    /// CHECK: read and written through entry_fees_held / sync_entry_fees
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
+
+    /// PDA signer owning the vault ATA
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
+
+    /// Vault balance backing the distribution
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimDistribution<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    /// PDA signer owning the vault ATA
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Race distribution holding the merkle root and claimed bitmap
+    #[account(
+        mut,
+        seeds = [b"race_distribution", config.key().as_ref(), &race_distribution.race_id_hash, mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub race_distribution: Box<Account<'info, RaceDistribution>>,
+
+    /// Recipient wallet named in the leaf
+    /// CHECK: bound by the merkle proof
+    pub recipient: UncheckedAccount<'info>,
+
+    /// Lifetime points ledger for the recipient (created on first use)
+    #[account(
+        mut,
+        seeds = [b"racer_stats", config.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    /// CHECK: created and written through record_stats
+    pub racer_stats: UncheckedAccount<'info>,
+
+    /// Points ledger for the recipient in the distribution's season (unused without one)
+    #[account(
+        mut,
+        seeds = [b"season_stats", config.key().as_ref(), recipient.key().as_ref(), &race_distribution.season_id.to_le_bytes()],
+        bump
+    )]
+    /// CHECK: created and written through record_stats
+    pub season_stats: UncheckedAccount<'info>,
+
+    /// Leaderboard of the active season (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"leaderboard", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub leaderboard: Option<AccountLoader<'info, Leaderboard>>,
+
+    /// Recipient ATA (auto-created if needed)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Global payout registry (tracks all payouts)
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    /// Active season the claim counts towards (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    /// Payer for transaction fees (can be anyone)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRaceDistribution<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = authority,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Admin authority (receives the reclaimed rent)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: must be accepted by config (constraint above)
+    pub mint: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"race_distribution", config.key().as_ref(), &race_distribution.race_id_hash, mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub race_distribution: Box<Account<'info, RaceDistribution>>,
+
+    /// Race the distribution was posted against; gets the unclaimed amount back
+    #[account(
+        mut,
+        seeds = [b"race", config.key().as_ref(), &race_distribution.race_id_hash],
+        bump
+    )]
+    pub race: Box<Account<'info, Race>>,
+
+    /// Season the distribution counts towards; required when it has one
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &race_distribution.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    /// Entry fees held for unsettled races in this mint (empty until its first entrant)
+    #[account(
+        mut,
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    /// CHECK: read and written through entry_fees_held / sync_entry_fees
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
+
+    /// Global payout registry (tracks all payouts)
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+}
+
+#[derive(Accounts)]
+#[instruction(race_id_hash: [u8; 32])]
+pub struct CancelPayout<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = authority,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Admin authority (only admin can cancel payouts)
+    pub authority: Signer<'info>,
+
+    /// Payout mint, or SOL_DENOMINATION for lamport payouts
+    /// CHECK: must be accepted by config (constraint above)
+    pub mint: UncheckedAccount<'info>,
+
+    /// Recipient of the payout being cancelled
+    /// CHECK: used only as a seed
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"receipt",
+            config.key().as_ref(),
+            &race_id_hash,
+            recipient.key().as_ref(),
+            mint_seed(&config, &mint.key()).as_slice()
+        ],
+        bump
+    )]
+    pub payout_receipt: Account<'info, PayoutReceipt>,
+
+    /// Race the payout was registered against; required exactly when the receipt has one
+    #[account(
+        mut,
+        seeds = [b"race", config.key().as_ref(), &race_id_hash],
+        bump
+    )]
+    pub race: Option<Box<Account<'info, Race>>>,
+
+    /// Season the payout counts towards; required when the receipt has one
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &payout_receipt.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    /// Lifetime points ledger for the recipient; required when the receipt has a race
+    #[account(
+        mut,
+        seeds = [b"racer_stats", config.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub racer_stats: Option<Box<Account<'info, RacerStats>>>,
+
+    /// Points ledger for the recipient in the payout's season; required when the
+    /// receipt has both a race and a season
+    #[account(
+        mut,
+        seeds = [b"season_stats", config.key().as_ref(), recipient.key().as_ref(), &payout_receipt.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season_stats: Option<Box<Account<'info, RacerStats>>>,
+
+    /// Leaderboard of the active season (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"leaderboard", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub leaderboard: Option<AccountLoader<'info, Leaderboard>>,
+
+    /// Entry fees held for unsettled races in this mint (empty until its first entrant)
+    #[account(
+        mut,
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    /// CHECK: read and written through entry_fees_held / sync_entry_fees
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"payout_registry", config.key().as_ref(), recipient.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub payout_registry: Account<'info, PayoutRegistry>,
+
+    /// Global payout registry (tracks all payouts)
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+}
+
+#[derive(Accounts)]
+#[instruction(race_id_hash: [u8; 32])]
+pub struct AmendPayout<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = authority,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Admin authority (only admin can amend payouts)
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Recipient of the payout being amended
+    /// CHECK: used only as a seed
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"receipt",
+            config.key().as_ref(),
+            &race_id_hash,
+            recipient.key().as_ref(),
+            mint_seed(&config, &mint.key()).as_slice()
+        ],
+        bump
+    )]
+    pub payout_receipt: Account<'info, PayoutReceipt>,
+
+    /// Race the payout was registered against; required exactly when the receipt has one
+    #[account(
+        mut,
+        seeds = [b"race", config.key().as_ref(), &race_id_hash],
+        bump
+    )]
+    pub race: Option<Box<Account<'info, Race>>>,
+
+    /// Season the payout counts towards; required when the receipt has one
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &payout_receipt.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    /// Lifetime points ledger for the recipient; required when the receipt has a race
+    #[account(
+        mut,
+        seeds = [b"racer_stats", config.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub racer_stats: Option<Box<Account<'info, RacerStats>>>,
+
+    /// Points ledger for the recipient in the payout's season; required when the
+    /// receipt has both a race and a season
+    #[account(
+        mut,
+        seeds = [b"season_stats", config.key().as_ref(), recipient.key().as_ref(), &payout_receipt.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season_stats: Option<Box<Account<'info, RacerStats>>>,
+
+    /// Leaderboard of the active season (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"leaderboard", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub leaderboard: Option<AccountLoader<'info, Leaderboard>>,
+
+    #[account(
+        mut,
+        seeds = [b"payout_registry", config.key().as_ref(), recipient.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub payout_registry: Account<'info, PayoutRegistry>,
+
+    /// Global payout registry (tracks all payouts)
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    /// Global referral registry (counts towards outstanding obligations)
+    #[account(
+        seeds = [b"global_referral_registry", config.key().as_ref()],
+        bump
+    )]
+    pub global_referral_registry: Account<'info, GlobalReferralRegistry>,
+
+    /// Entry fees held for unsettled races in this mint (empty until its first entrant)
+    #[account(
+        mut,
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    /// CHECK: read and written through entry_fees_held / sync_entry_fees
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
+
+    /// PDA signer owning the vault ATA
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
+
+    /// Vault balance backing an increased amount
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(race_id_hash: [u8; 32])]
+pub struct AmendSolPayout<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = authority,
+        constraint = config.accepts_mint(&SOL_DENOMINATION) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Admin authority (only admin can amend payouts)
+    pub authority: Signer<'info>,
+
+    /// Recipient of the payout being amended
+    /// CHECK: used only as a seed
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"receipt",
+            config.key().as_ref(),
+            &race_id_hash,
+            recipient.key().as_ref(),
+            mint_seed(&config, &SOL_DENOMINATION).as_slice()
+        ],
+        bump
+    )]
+    pub payout_receipt: Account<'info, PayoutReceipt>,
+
+    /// Race the payout was registered against; required exactly when the receipt has one
+    #[account(
+        mut,
+        seeds = [b"race", config.key().as_ref(), &race_id_hash],
+        bump
+    )]
+    pub race: Option<Box<Account<'info, Race>>>,
+
+    /// Season the payout counts towards; required when the receipt has one
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &payout_receipt.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    /// Lifetime points ledger for the recipient; required when the receipt has a race
+    #[account(
+        mut,
+        seeds = [b"racer_stats", config.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub racer_stats: Option<Box<Account<'info, RacerStats>>>,
+
+    /// Points ledger for the recipient in the payout's season; required when the
+    /// receipt has both a race and a season
+    #[account(
+        mut,
+        seeds = [b"season_stats", config.key().as_ref(), recipient.key().as_ref(), &payout_receipt.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season_stats: Option<Box<Account<'info, RacerStats>>>,
+
+    /// Leaderboard of the active season (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"leaderboard", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub leaderboard: Option<AccountLoader<'info, Leaderboard>>,
+
+    #[account(
+        mut,
+        seeds = [b"payout_registry", config.key().as_ref(), recipient.key().as_ref(), mint_seed(&config, &SOL_DENOMINATION).as_slice()],
+        bump
+    )]
+    pub payout_registry: Account<'info, PayoutRegistry>,
+
+    /// Global payout registry for SOL
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &SOL_DENOMINATION).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    /// Entry fee registry for SOL (stays empty: SOL races take no entry fee)
+    #[account(
+        mut,
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &SOL_DENOMINATION).as_slice()],
+        bump
+    )]
+    /// CHECK: read and written through entry_fees_held / sync_entry_fees
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
+
+    /// Lamport pool backing an increased amount
+    #[account(
+        seeds = [b"sol_vault", config.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(race_id_hash: [u8; 32])]
+pub struct SweepExpiredPayout<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Payout mint, or SOL_DENOMINATION for lamport payouts
+    /// CHECK: must be accepted by config (constraint above)
+    pub mint: UncheckedAccount<'info>,
+
+    /// Recipient of the expired payout
+    /// CHECK: used only as a seed
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"receipt",
+            config.key().as_ref(),
+            &race_id_hash,
+            recipient.key().as_ref(),
+            mint_seed(&config, &mint.key()).as_slice()
+        ],
+        bump
+    )]
+    pub payout_receipt: Account<'info, PayoutReceipt>,
+
+    /// Race the payout was registered against; required exactly when the receipt has one
+    #[account(
+        mut,
+        seeds = [b"race", config.key().as_ref(), &race_id_hash],
+        bump
+    )]
+    pub race: Option<Box<Account<'info, Race>>>,
+
+    /// Season the payout counts towards; required when the receipt has one
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &payout_receipt.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    /// Entry fees held for unsettled races in this mint (empty until its first entrant)
+    #[account(
+        mut,
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    /// CHECK: read and written through entry_fees_held / sync_entry_fees
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"payout_registry", config.key().as_ref(), recipient.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub payout_registry: Account<'info, PayoutRegistry>,
+
+    /// Global payout registry (tracks all payouts)
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
 }
 
 #[derive(Accounts)]
-pub struct Initialize<'info> {
+#[instruction(race_id_hash: [u8; 32])]
+pub struct CloseReceipts<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = authority,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Admin authority (receives the reclaimed rent)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Payout mint, or SOL_DENOMINATION for lamport payouts
+    /// CHECK: must be accepted by config (constraint above)
+    pub mint: UncheckedAccount<'info>,
+
+    /// Replaces the closed receipts for replay protection
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RaceTombstone::SIZE,
+        seeds = [b"race_tombstone", config.key().as_ref(), &race_id_hash],
+        bump
+    )]
+    pub race_tombstone: Account<'info, RaceTombstone>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct ClaimPendingPayouts<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    /// PDA signer owning the vault ATA
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// Recipient wallet to receive payout
+    /// CHECK: validated by constraint below
+    pub recipient: UncheckedAccount<'info>,
+
+    /// Recipient ATA (auto-created if needed)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// Payout registry (tracks total pending payouts for this recipient)
+    #[account(
+        mut,
+        seeds = [b"payout_registry", config.key().as_ref(), recipient.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump,
+        constraint = payout_registry.recipient == recipient.key()
+    )]
+    pub payout_registry: Account<'info, PayoutRegistry>,
+
+    /// Global payout registry (tracks all payouts)
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    /// Active season the claim counts towards (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    /// Payer for transaction fees (can be anyone)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct ClaimPendingSolPayouts<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&SOL_DENOMINATION) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Lamport pool paying the claim
+    #[account(
+        mut,
+        seeds = [b"sol_vault", config.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    /// Recipient wallet receiving the lamports
+    /// CHECK: validated by constraint below
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    /// Payout registry (tracks total pending SOL payouts for this recipient)
+    #[account(
+        mut,
+        seeds = [b"payout_registry", config.key().as_ref(), recipient.key().as_ref(), mint_seed(&config, &SOL_DENOMINATION).as_slice()],
+        bump,
+        constraint = payout_registry.recipient == recipient.key()
+    )]
+    pub payout_registry: Account<'info, PayoutRegistry>,
+
+    /// Global payout registry for SOL
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &SOL_DENOMINATION).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    /// Active season the claim counts towards (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(race_id_hash: [u8; 32], points: u64, amount: u64, expiry: i64, nonce: u64)]
+pub struct ClaimWithVoucher<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    /// PDA signer owning the vault ATA
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Recipient named in the voucher (pays for the nonce record)
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    /// Recipient ATA (auto-created if needed)
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// One record per voucher nonce. Prevents replays.
+    #[account(
+        init,
+        payer = recipient,
+        space = 8 + VoucherClaim::SIZE,
+        seeds = [b"voucher", config.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub voucher_claim: Account<'info, VoucherClaim>,
+
+    /// Global payout registry (pending payouts stay reserved)
+    #[account(
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    /// Active season the voucher counts towards, as registered and claimed (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"season", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub season: Option<Box<Account<'info, Season>>>,
+
+    /// Leaderboard of the active season (required while a season is active)
+    #[account(
+        mut,
+        seeds = [b"leaderboard", config.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    pub leaderboard: Option<AccountLoader<'info, Leaderboard>>,
+
+    /// Lifetime points ledger for the recipient (created on first use)
+    #[account(
+        mut,
+        seeds = [b"racer_stats", config.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    /// CHECK: created and written through record_stats
+    pub racer_stats: UncheckedAccount<'info>,
+
+    /// Points ledger for the recipient in the active season (unused outside its window)
+    #[account(
+        mut,
+        seeds = [b"season_stats", config.key().as_ref(), recipient.key().as_ref(), &config.season_id.to_le_bytes()],
+        bump
+    )]
+    /// CHECK: created and written through record_stats
+    pub season_stats: UncheckedAccount<'info>,
+
+    /// Global referral registry (pending bonuses stay reserved)
+    #[account(
+        seeds = [b"global_referral_registry", config.key().as_ref()],
+        bump
+    )]
+    pub global_referral_registry: Account<'info, GlobalReferralRegistry>,
+
+    /// Entry fees held for unsettled races in this mint (empty until its first entrant)
+    #[account(
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    /// CHECK: read and written through entry_fees_held / sync_entry_fees
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
+
+    /// Instructions sysvar holding the ed25519 precompile instruction
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: address checked above
+    pub instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct Reconcile<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = authority,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Only authority can reconcile
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSurplus<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted,
+        constraint = config.has_role(Role::Treasurer, &treasurer.key()) @ VaultError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Treasurer (only the treasurer can withdraw surplus)
+    pub treasurer: Signer<'info>,
+
+    /// PDA signer owning the vault ATA
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// Destination for the withdrawn surplus
+    #[account(
+        mut,
+        token::mint = mint
+    )]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// Global payout registry (pending payouts stay reserved)
     #[account(
-        init,
-        payer = authority,
-        space = 8 + Config::SIZE,
-        seeds = [b"config", mint.key().as_ref()],
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
         bump
     )]
-    pub config: Account<'info, Config>,
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
 
+    /// Global referral registry (pending bonuses stay reserved)
     #[account(
-        seeds = [b"vault_signer", config.key().as_ref()],
+        seeds = [b"global_referral_registry", config.key().as_ref()],
         bump
-        // no init: it's just the signer PDA, no lamports
     )]
-    /// CHECK: PDA signer, no data
-    pub vault_signer: UncheckedAccount<'info>,
-
-    pub mint: Account<'info, Mint>,
+    pub global_referral_registry: Account<'info, GlobalReferralRegistry>,
 
-    /// Program-owned vault ATA (created if missing)
+    /// Entry fees held for unsettled races in this mint (empty until its first entrant)
     #[account(
-        init_if_needed,
-        payer = authority,
-        associated_token::mint = mint,
-        associated_token::authority = vault_signer
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
     )]
-    pub vault_token: Account<'info, TokenAccount>,
+    /// CHECK: read and written through entry_fees_held / sync_entry_fees
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
 
-    /// Global payout registry (tracks all payouts across all recipients)
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct TransferAuthority<'info> {
     #[account(
-        init,
-        payer = authority,
-        space = 8 + GlobalPayoutRegistry::SIZE,
-        seeds = [b"global_payout_registry", config.key().as_ref()],
-        bump
+        mut,
+        seeds = [b"config", mint.key().as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = authority
     )]
-    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+    pub config: Account<'info, Config>,
 
-    #[account(mut)]
     pub authority: Signer<'info>,
 
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub rent: Sysvar<'info, Rent>,
+    pub mint: InterfaceAccount<'info, Mint>,
 }
 
 #[derive(Accounts)]
-pub struct Deposit<'info> {
+pub struct AcceptAuthority<'info> {
     #[account(
-        seeds = [b"config", mint.key().as_ref()],
-        bump
+        mut,
+        seeds = [b"config", mint.key().as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.pending_authority == pending_authority.key() @ VaultError::InvalidAuthority
     )]
     pub config: Account<'info, Config>,
 
-    /// PDA signer (authority of the vault token)
+    /// Proposed authority (must sign to prove key ownership)
+    pub pending_authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuthorityTransfer<'info> {
     #[account(
-        seeds = [b"vault_signer", config.key().as_ref()],
-        bump = config.vault_signer_bump
+        mut,
+        seeds = [b"config", mint.key().as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = authority
     )]
-    /// CHECK: PDA signer
-    pub vault_signer: UncheckedAccount<'info>,
+    pub config: Account<'info, Config>,
+
+    pub authority: Signer<'info>,
 
-    pub mint: Account<'info, Mint>,
+    pub mint: InterfaceAccount<'info, Mint>,
+}
 
+#[derive(Accounts)]
+pub struct ManageRole<'info> {
     #[account(
         mut,
-        associated_token::mint = mint,
-        associated_token::authority = vault_signer
+        seeds = [b"config", mint.key().as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = authority
     )]
-    pub vault_token: Account<'info, TokenAccount>,
+    pub config: Account<'info, Config>,
 
-    #[account(mut)]
-    pub depositor: Signer<'info>,
+    /// Admin authority (only admin can grant or revoke roles)
+    pub authority: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+}
 
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
     #[account(
         mut,
-        associated_token::mint = mint,
-        associated_token::authority = depositor
+        seeds = [b"config", mint.key().as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.authority == signer.key()
+            || config.has_role(Role::Pauser, &signer.key()) @ VaultError::Unauthorized
     )]
-    pub depositor_token: Account<'info, TokenAccount>,
+    pub config: Account<'info, Config>,
 
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// Pauser or admin authority (checked per parameter)
+    pub signer: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
 }
 
 #[derive(Accounts)]
-#[instruction(race_id: String, race_id_hash: [u8; 32])]
-pub struct RegisterPayout<'info> {
+pub struct Decommission<'info> {
     #[account(
-        seeds = [b"config", mint.key().as_ref()],
+        mut,
+        seeds = [b"config", mint.key().as_ref(), vault_id_seed(config.vault_id).as_slice()],
         bump,
         has_one = authority,
-        has_one = mint
+        has_one = mint,
+        close = authority
     )]
     pub config: Account<'info, Config>,
 
-    /// Admin authority (only admin can register payouts)
+    /// Writable so withheld Token-2022 transfer fees can be harvested to it
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
 
-    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
 
-    /// Recipient wallet to receive payout
-    /// CHECK: validated by ATA derivation below
-    pub recipient: UncheckedAccount<'info>,
+    /// PDA signer owning the vault ATA
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
 
-    /// One receipt per (race_id_hash, recipient). Prevents replays.
     #[account(
-        init,
-        payer = authority,
-        space = 8 + PayoutReceipt::SIZE,
-        seeds = [
-            b"receipt", 
-            config.key().as_ref(), 
-            &race_id_hash,
-            recipient.key().as_ref()
-        ],
-        bump
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
     )]
-    pub payout_receipt: Account<'info, PayoutReceipt>,
+    pub vault_token: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// Payout registry (tracks total pending payouts for this recipient)
+    /// Receives the remaining vault balance
     #[account(
-        init_if_needed,
-        payer = authority,
-        space = 8 + PayoutRegistry::SIZE,
-        seeds = [b"payout_registry", config.key().as_ref(), recipient.key().as_ref()],
-        bump
+        mut,
+        token::mint = mint,
+        constraint = treasury_token.key() != vault_token.key() @ VaultError::InvalidTreasury
     )]
-    pub payout_registry: Account<'info, PayoutRegistry>,
+    pub treasury_token: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// Global payout registry (tracks all payouts)
     #[account(
         mut,
         seeds = [b"global_payout_registry", config.key().as_ref()],
-        bump
+        bump,
+        close = authority
     )]
     pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
 
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+    #[account(
+        mut,
+        seeds = [b"global_referral_registry", config.key().as_ref()],
+        bump,
+        close = authority
+    )]
+    pub global_referral_registry: Account<'info, GlobalReferralRegistry>,
+
+    /// Entry fees held for unsettled races in this mint (empty until its first entrant)
+    #[account(
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    /// CHECK: read and written through entry_fees_held / sync_entry_fees
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-#[instruction(recipient: Pubkey)]
-pub struct ClaimPendingPayouts<'info> {
+pub struct AddMint<'info> {
     #[account(
-        seeds = [b"config", mint.key().as_ref()],
+        mut,
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
         bump,
-        has_one = mint
+        has_one = authority
     )]
     pub config: Account<'info, Config>,
 
-    /// PDA signer owning the vault ATA
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// PDA signer owning the vault ATAs
     #[account(
         seeds = [b"vault_signer", config.key().as_ref()],
         bump = config.vault_signer_bump
@@ -590,66 +5644,52 @@ pub struct ClaimPendingPayouts<'info> {
     /// CHECK: PDA signer
     pub vault_signer: UncheckedAccount<'info>,
 
-    pub mint: Account<'info, Mint>,
-
-    #[account(
-        mut,
-        associated_token::mint = mint,
-        associated_token::authority = vault_signer
-    )]
-    pub vault_token: Account<'info, TokenAccount>,
-
-    /// Recipient wallet to receive payout
-    /// CHECK: validated by constraint below
-    pub recipient: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
 
-    /// Recipient ATA (auto-created if needed)
+    /// Vault ATA for the new mint (created if missing)
     #[account(
         init_if_needed,
-        payer = payer,
+        payer = authority,
         associated_token::mint = mint,
-        associated_token::authority = recipient
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
     )]
-    pub recipient_token: Account<'info, TokenAccount>,
+    pub vault_token: InterfaceAccount<'info, TokenAccount>,
 
-    /// Payout registry (tracks total pending payouts for this recipient)
-    #[account(
-        mut,
-        constraint = payout_registry.recipient == recipient.key()
-    )]
-    pub payout_registry: Account<'info, PayoutRegistry>,
-
-    /// Global payout registry (tracks all payouts)
+    /// Global payout registry for the new mint
     #[account(
-        mut,
-        seeds = [b"global_payout_registry", config.key().as_ref()],
+        init,
+        payer = authority,
+        space = 8 + GlobalPayoutRegistry::SIZE,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint.key().as_ref()],
         bump
     )]
     pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
 
-    /// Payer for transaction fees (can be anyone)
-    #[account(mut)]
-    pub payer: Signer<'info>,
-
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct Reconcile<'info> {
+pub struct RemoveMint<'info> {
     #[account(
-        seeds = [b"config", mint.key().as_ref()],
+        mut,
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
         bump,
         has_one = authority,
-        has_one = mint
+        constraint = config.accepted_mints.contains(&mint.key()) @ VaultError::MintNotAccepted
     )]
     pub config: Account<'info, Config>,
 
-    /// Only authority can reconcile
+    /// Writable so withheld Token-2022 transfer fees can be harvested to it
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
     pub authority: Signer<'info>,
 
+    /// PDA signer owning the vault ATAs
     #[account(
         seeds = [b"vault_signer", config.key().as_ref()],
         bump = config.vault_signer_bump
@@ -657,82 +5697,133 @@ pub struct Reconcile<'info> {
     /// CHECK: PDA signer
     pub vault_signer: UncheckedAccount<'info>,
 
-    pub mint: Account<'info, Mint>,
-
     #[account(
+        mut,
         associated_token::mint = mint,
-        associated_token::authority = vault_signer
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
     )]
-    pub vault_token: Account<'info, TokenAccount>,
-}
+    pub vault_token: Box<InterfaceAccount<'info, TokenAccount>>,
 
-#[derive(Accounts)]
-pub struct TransferAuthority<'info> {
+    /// Receives the remaining vault balance in this mint
     #[account(
         mut,
-        seeds = [b"config", mint.key().as_ref()],
+        token::mint = mint,
+        constraint = treasury_token.key() != vault_token.key() @ VaultError::InvalidTreasury
+    )]
+    pub treasury_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint.key().as_ref()],
         bump,
-        has_one = authority
+        close = authority
     )]
-    pub config: Account<'info, Config>,
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
 
-    pub authority: Signer<'info>,
+    /// Entry fees held for unsettled races in this mint (empty until its first entrant)
+    #[account(
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: read through entry_fees_held
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
 
-    pub mint: Account<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateConfig<'info> {
+pub struct EnableSol<'info> {
     #[account(
         mut,
-        seeds = [b"config", mint.key().as_ref()],
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
         bump,
         has_one = authority
     )]
     pub config: Account<'info, Config>,
 
+    #[account(mut)]
     pub authority: Signer<'info>,
 
-    pub mint: Account<'info, Mint>,
+    /// Lamport pool backing SOL payouts (funded by deposit_sol)
+    #[account(
+        seeds = [b"sol_vault", config.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    /// Global payout registry for SOL
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GlobalPayoutRegistry::SIZE,
+        seeds = [b"global_payout_registry", config.key().as_ref(), SOL_DENOMINATION.as_ref()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct Close<'info> {
+pub struct DisableSol<'info> {
     #[account(
         mut,
-        seeds = [b"config", mint.key().as_ref()],
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
         bump,
         has_one = authority,
-        close = authority
+        constraint = config.accepted_mints.contains(&SOL_DENOMINATION) @ VaultError::MintNotAccepted
     )]
     pub config: Account<'info, Config>,
 
-    pub mint: Account<'info, Mint>,
-
     #[account(mut)]
     pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"sol_vault", config.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    /// Receives the remaining SOL vault lamports
+    #[account(
+        mut,
+        constraint = treasury.key() != sol_vault.key() @ VaultError::InvalidTreasury
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_payout_registry", config.key().as_ref(), SOL_DENOMINATION.as_ref()],
+        bump,
+        close = authority
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 #[instruction(race_id: String, referrer: Pubkey, referee: Pubkey)]
 pub struct RegisterReferralBonus<'info> {
     #[account(
-        seeds = [b"config", mint.key().as_ref()],
+        seeds = [b"config", mint.key().as_ref(), vault_id_seed(config.vault_id).as_slice()],
         bump,
-        has_one = authority
+        constraint = config.has_role(Role::Operator, &operator.key()) @ VaultError::Unauthorized
     )]
     pub config: Account<'info, Config>,
 
-    /// Admin authority (only admin can register referral bonuses)
+    /// Payout operator (only the operator can register referral bonuses)
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub operator: Signer<'info>,
 
-    pub mint: Account<'info, Mint>,
+    pub mint: InterfaceAccount<'info, Mint>,
 
     /// Referral bonus account (unique per race_id + referrer + referee combination)
     #[account(
         init,
-        payer = authority,
+        payer = operator,
         space = 8 + ReferralBonus::SIZE,
         seeds = [
             b"referral_bonus",
@@ -743,20 +5834,59 @@ pub struct RegisterReferralBonus<'info> {
         ],
         bump
     )]
-    pub referral_bonus: Account<'info, ReferralBonus>,
+    pub referral_bonus: Account<'info, ReferralBonus>,
+
+    /// Referrer registry (tracks total pending bonuses for this referrer)
+    #[account(
+        init_if_needed,
+        payer = operator,
+        space = 8 + ReferrerRegistry::SIZE,
+        seeds = [b"referrer_registry", config.key().as_ref(), referrer.as_ref()],
+        bump
+    )]
+    pub referrer_registry: Account<'info, ReferrerRegistry>,
+
+    /// Global referral registry (tracks all referral bonuses)
+    #[account(
+        mut,
+        seeds = [b"global_referral_registry", config.key().as_ref()],
+        bump
+    )]
+    pub global_referral_registry: Account<'info, GlobalReferralRegistry>,
+
+    /// Entry fees held for unsettled races in this mint (empty until its first entrant)
+    #[account(
+        seeds = [b"global_entry_fee_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    /// CHECK: read and written through entry_fees_held / sync_entry_fees
+    pub global_entry_fee_registry: UncheckedAccount<'info>,
 
-    /// Referrer registry (tracks total pending bonuses for this referrer)
+    /// Global payout registry (counts towards outstanding obligations)
     #[account(
-        init_if_needed,
-        payer = authority,
-        space = 8 + ReferrerRegistry::SIZE,
-        seeds = [b"referrer_registry", config.key().as_ref(), referrer.as_ref()],
+        seeds = [b"global_payout_registry", config.key().as_ref()],
         bump
     )]
-    pub referrer_registry: Account<'info, ReferrerRegistry>,
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+
+    /// PDA signer owning the vault ATA
+    #[account(
+        seeds = [b"vault_signer", config.key().as_ref()],
+        bump = config.vault_signer_bump
+    )]
+    /// CHECK: PDA signer
+    pub vault_signer: UncheckedAccount<'info>,
+
+    /// Vault balance backing the new obligation
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: Box<InterfaceAccount<'info, TokenAccount>>,
 
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub rent: Sysvar<'info, Rent>,
 }
@@ -765,7 +5895,7 @@ pub struct RegisterReferralBonus<'info> {
 #[derive(Accounts)]
 pub struct ClaimPendingBonuses<'info> {
     #[account(
-        seeds = [b"config", mint.key().as_ref()],
+        seeds = [b"config", mint.key().as_ref(), vault_id_seed(config.vault_id).as_slice()],
         bump,
         has_one = mint
     )]
@@ -779,18 +5909,21 @@ pub struct ClaimPendingBonuses<'info> {
     /// CHECK: PDA signer
     pub vault_signer: UncheckedAccount<'info>,
 
-    pub mint: Account<'info, Mint>,
+    pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
         associated_token::mint = mint,
-        associated_token::authority = vault_signer
+        associated_token::authority = vault_signer,
+        associated_token::token_program = token_program
     )]
-    pub vault_token: Account<'info, TokenAccount>,
+    pub vault_token: InterfaceAccount<'info, TokenAccount>,
 
     /// Referrer registry (tracks total pending bonuses for this referrer)
     #[account(
         mut,
+        seeds = [b"referrer_registry", config.key().as_ref(), referrer.key().as_ref()],
+        bump,
         constraint = referrer_registry.referrer == referrer.key()
     )]
     pub referrer_registry: Account<'info, ReferrerRegistry>,
@@ -804,16 +5937,25 @@ pub struct ClaimPendingBonuses<'info> {
         init_if_needed,
         payer = payer,
         associated_token::mint = mint,
-        associated_token::authority = referrer
+        associated_token::authority = referrer,
+        associated_token::token_program = token_program
+    )]
+    pub referrer_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// Global referral registry (tracks all referral bonuses)
+    #[account(
+        mut,
+        seeds = [b"global_referral_registry", config.key().as_ref()],
+        bump
     )]
-    pub referrer_token: Account<'info, TokenAccount>,
+    pub global_referral_registry: Account<'info, GlobalReferralRegistry>,
 
     /// Payer for transaction fees (can be anyone)
     #[account(mut)]
     pub payer: Signer<'info>,
 
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub rent: Sysvar<'info, Rent>,
 }
@@ -821,16 +5963,18 @@ pub struct ClaimPendingBonuses<'info> {
 #[derive(Accounts)]
 pub struct GetPendingBonuses<'info> {
     #[account(
-        seeds = [b"config", mint.key().as_ref()],
+        seeds = [b"config", mint.key().as_ref(), vault_id_seed(config.vault_id).as_slice()],
         bump,
         has_one = mint
     )]
     pub config: Account<'info, Config>,
 
-    pub mint: Account<'info, Mint>,
+    pub mint: InterfaceAccount<'info, Mint>,
 
     /// Referrer registry (tracks total pending bonuses for this referrer)
     #[account(
+        seeds = [b"referrer_registry", config.key().as_ref(), referrer.key().as_ref()],
+        bump,
         constraint = referrer_registry.referrer == referrer.key()
     )]
     pub referrer_registry: Account<'info, ReferrerRegistry>,
@@ -843,16 +5987,18 @@ pub struct GetPendingBonuses<'info> {
 #[derive(Accounts)]
 pub struct GetAllBonuses<'info> {
     #[account(
-        seeds = [b"config", mint.key().as_ref()],
+        seeds = [b"config", mint.key().as_ref(), vault_id_seed(config.vault_id).as_slice()],
         bump,
         has_one = mint
     )]
     pub config: Account<'info, Config>,
 
-    pub mint: Account<'info, Mint>,
+    pub mint: InterfaceAccount<'info, Mint>,
 
     /// Referrer registry (tracks total pending bonuses for this referrer)
     #[account(
+        seeds = [b"referrer_registry", config.key().as_ref(), referrer.key().as_ref()],
+        bump,
         constraint = referrer_registry.referrer == referrer.key()
     )]
     pub referrer_registry: Account<'info, ReferrerRegistry>,
@@ -865,16 +6011,20 @@ pub struct GetAllBonuses<'info> {
 #[derive(Accounts)]
 pub struct GetPendingPayouts<'info> {
     #[account(
-        seeds = [b"config", mint.key().as_ref()],
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
         bump,
-        has_one = mint
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted
     )]
     pub config: Account<'info, Config>,
 
-    pub mint: Account<'info, Mint>,
+    /// Payout mint, or SOL_DENOMINATION for lamport payouts
+    /// CHECK: must be accepted by config (constraint above)
+    pub mint: UncheckedAccount<'info>,
 
     /// Payout registry (tracks total pending payouts for this recipient)
     #[account(
+        seeds = [b"payout_registry", config.key().as_ref(), recipient.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump,
         constraint = payout_registry.recipient == recipient.key()
     )]
     pub payout_registry: Account<'info, PayoutRegistry>,
@@ -884,97 +6034,679 @@ pub struct GetPendingPayouts<'info> {
     pub recipient: UncheckedAccount<'info>,
 }
 
-#[derive(Accounts)]
-pub struct GetGlobalPayoutStats<'info> {
-    #[account(
-        seeds = [b"config", mint.key().as_ref()],
-        bump,
-        has_one = mint
-    )]
-    pub config: Account<'info, Config>,
-
-    pub mint: Account<'info, Mint>,
+#[derive(Accounts)]
+pub struct GetGlobalPayoutStats<'info> {
+    #[account(
+        seeds = [b"config", config.mint.as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        constraint = config.accepts_mint(&mint.key()) @ VaultError::MintNotAccepted
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Payout mint, or SOL_DENOMINATION for lamport payouts
+    /// CHECK: must be accepted by config (constraint above)
+    pub mint: UncheckedAccount<'info>,
+
+    /// Global payout registry (tracks all payouts)
+    #[account(
+        seeds = [b"global_payout_registry", config.key().as_ref(), mint_seed(&config, &mint.key()).as_slice()],
+        bump
+    )]
+    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+}
+
+pub const BPS_DENOMINATOR: u16 = 10_000;
+pub const MAX_BATCH_PAYOUTS: usize = 15;  // Fits one transaction with its accounts in a lookup table
+pub const BATCH_ENTRY_ACCOUNTS: usize = 4;  // receipt, registry, racer stats, season stats
+pub const MAX_DISTRIBUTION_LEAVES: u32 = 65_536;  // 8 KiB claimed bitmap
+pub const MAX_MEMO_LEN: usize = 64;
+pub const LEGACY_PAYOUT_RECEIPT_SIZE: usize = 32 + 32 + 8 + 8 + 8;  // 88 bytes, before status and sequence
+pub const LEGACY_PAYOUT_REGISTRY_SIZE: usize = 32 + 8 + 8 + 4 + 8;  // 60 bytes, before claimed_count
+pub const LEGACY_REFERRAL_BONUS_SIZE: usize = 4 + 32 + 32 + 32 + 8 + 1 + 8;  // 117 bytes, before claimed_at
+pub const MAX_ACCEPTED_MINTS: usize = 8;
+pub const MAX_PRIZE_PLACES: usize = 32;
+pub const POINTS_RATE_DECIMALS: u8 = 6;
+pub const SOL_DECIMALS: u8 = 9;
+pub const LEADERBOARD_SIZE: usize = 100;
+/// Stands in for a mint address wherever a payout is denominated in native SOL
+pub const SOL_DENOMINATION: Pubkey = system_program::ID;
+
+#[derive(Accounts)]
+pub struct GetGlobalReferralStats<'info> {
+    #[account(
+        seeds = [b"config", mint.key().as_ref(), vault_id_seed(config.vault_id).as_slice()],
+        bump,
+        has_one = mint
+    )]
+    pub config: Account<'info, Config>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Global referral registry (tracks all referral bonuses)
+    #[account(
+        seeds = [b"global_referral_registry", config.key().as_ref()],
+        bump
+    )]
+    pub global_referral_registry: Account<'info, GlobalReferralRegistry>,
+}
+
+/// New fields are only ever appended, and an all-zero value must be a safe
+/// default, so `migrate` can grow accounts created with an older layout
+#[account]
+pub struct Config {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub vault_signer_bump: u8,
+    pub paused: bool,
+    pub pending_authority: Pubkey,  // Pubkey::default() when no transfer is proposed
+    pub operator: Pubkey,   // Registers payouts and referral bonuses
+    pub pauser: Pubkey,     // Pauses and unpauses the program
+    pub treasurer: Pubkey,  // Manages vault funds
+    pub reserve_ratio_bps: u16,  // Share of the vault balance kept unallocated
+    pub claim_window: i64,  // Seconds a payout stays claimable; 0 = never expires
+    pub receipt_close_horizon: i64,  // Minimum receipt age in seconds before close_receipts; 0 = disabled
+    pub vault_id: u64,  // Namespace within the mint; 0 = the original single vault
+    pub accepted_mints: Vec<Pubkey>,  // Payout mints besides `mint`, each with its own vault ATA (SOL_DENOMINATION uses the sol_vault PDA)
+    pub voucher_signer: Pubkey,  // Signs claim_with_voucher vouchers; Pubkey::default() = vouchers disabled
+    pub points_rate: u64,  // Whole tokens per point, scaled by 10^POINTS_RATE_DECIMALS
+    pub amounts_from_points: bool,  // Payout registration derives amounts from points
+    pub season_id: u64,  // Active Season, set by open_season and cleared by close_season; 0 = none
+}
+impl Config {
+    pub const SIZE: usize = 32 + 32 + 1 + 1 + 32 + 32 + 32 + 32 + 2 + 8 + 8 + 8
+        + 4 + 32 * MAX_ACCEPTED_MINTS + 32 + 8 + 1 + 8;  // 529 bytes
+
+    /// The primary mint or one of the allowlisted payout mints
+    pub fn accepts_mint(&self, mint: &Pubkey) -> bool {
+        *mint == self.mint || self.accepted_mints.contains(mint)
+    }
+
+    /// Token amount, in base units of a mint with `decimals`, worth `points`
+    pub fn points_to_amount(&self, points: u64, decimals: u8) -> Result<u64> {
+        points_amount(points, self.points_rate, decimals)
+    }
+
+    /// Expiry timestamp for a payout registered at `now` (0 = never expires)
+    pub fn payout_expiry(&self, now: i64) -> Result<i64> {
+        if self.claim_window == 0 {
+            return Ok(0);
+        }
+        Ok(now.checked_add(self.claim_window).ok_or(VaultError::Overflow)?)
+    }
+
+    /// Largest total of outstanding obligations the given vault balance can back
+    pub fn obligation_capacity(&self, vault_balance: u64) -> u64 {
+        let reserve = vault_balance as u128 * self.reserve_ratio_bps as u128 / BPS_DENOMINATOR as u128;
+        vault_balance - reserve as u64
+    }
+
+    pub fn role_holder(&self, role: Role) -> Pubkey {
+        match role {
+            Role::Operator => self.operator,
+            Role::Pauser => self.pauser,
+            Role::Treasurer => self.treasurer,
+        }
+    }
+
+    pub fn set_role_holder(&mut self, role: Role, holder: Pubkey) {
+        match role {
+            Role::Operator => self.operator = holder,
+            Role::Pauser => self.pauser = holder,
+            Role::Treasurer => self.treasurer = holder,
+        }
+    }
+
+    /// Revoked roles hold Pubkey::default(), which can never sign
+    pub fn has_role(&self, role: Role, key: &Pubkey) -> bool {
+        let holder = self.role_holder(role);
+        holder != Pubkey::default() && holder == *key
+    }
+}
+
+/// Delegated keys; the admin is `Config.authority` and is transferred separately
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Role {
+    Operator,
+    Pauser,
+    Treasurer,
+}
+
+#[account]
+pub struct PayoutReceipt {
+    pub race_id_hash: [u8; 32],  // Hash of the race_id (CUID)
+    pub recipient: Pubkey,
+    pub points: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+    pub status: PayoutStatus,
+    pub sequence: u32,  // Index of this payout in the recipient's PayoutRegistry
+    pub expires_at: i64,  // 0 = never expires
+    pub has_race: bool,  // Registered against the Race at race_id_hash; false for legacy payouts
+    pub season_id: u64,  // Season the payout counts towards; 0 = none
+}
+impl PayoutReceipt {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 8 + 1 + 4 + 8 + 1 + 8;  // 110 bytes
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PayoutStatus {
+    Active,
+    Amended,
+    Cancelled,
+    Expired,
+}
+
+/// One racer in a settle_race finishing order
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Finisher {
+    pub recipient: Pubkey,
+    pub points: u64,
+}
+
+#[account]
+pub struct PrizeTable {
+    pub table_id: u64,
+    pub mode: PrizeMode,
+    pub points_rate: u64,  // PointsRate: token base units per point
+    pub place_shares_bps: Vec<u16>,  // PercentOfPool: share of the prize budget per place
+}
+impl PrizeTable {
+    pub const SIZE: usize = 8 + 1 + 8 + 4 + 2 * MAX_PRIZE_PLACES;  // 85 bytes
+
+    /// Amount owed to the finisher at `place` (0-based) for a race with `prize_pool`
+    pub fn payout_for(&self, place: usize, points: u64, prize_pool: u64) -> Result<u64> {
+        let amount = match self.mode {
+            PrizeMode::PercentOfPool => match self.place_shares_bps.get(place) {
+                Some(share) => (prize_pool as u128 * *share as u128 / BPS_DENOMINATOR as u128) as u64,
+                None => 0,
+            },
+            PrizeMode::PointsRate => points.checked_mul(self.points_rate).ok_or(VaultError::Overflow)?,
+        };
+        Ok(amount)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PrizeMode {
+    PercentOfPool,
+    PointsRate,
+}
+
+/// One finisher in a register_race_payouts batch
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PayoutEntry {
+    pub recipient: Pubkey,
+    pub points: u64,
+    pub amount: u64,
+}
+
+#[account]
+pub struct ReferralBonus {
+    pub race_id: String,
+    pub referrer: Pubkey,
+    pub referee: Pubkey,
+    pub amount: u64,
+    pub claimed: bool,
+    pub timestamp: i64,
+    pub claimed_at: i64,  // 0 until claimed
+}
+impl ReferralBonus {
+    pub const SIZE: usize = 4 + 32 + 32 + 32 + 8 + 1 + 8 + 8;  // 125 bytes (4 for string length)
+}
+
+#[account]
+pub struct ReferrerRegistry {
+    pub referrer: Pubkey,
+    pub total_pending: u64,
+    pub total_claimed: u64,
+    pub bonus_count: u32,
+    pub last_updated: i64,
+}
+impl ReferrerRegistry {
+    pub const SIZE: usize = 32 + 8 + 8 + 4 + 8;  // 60 bytes
+}
+
+#[account]
+pub struct PayoutRegistry {
+    pub recipient: Pubkey,
+    pub total_pending: u64,
+    pub total_claimed: u64,
+    pub payout_count: u32,
+    pub last_updated: i64,
+    pub claimed_count: u32,  // Payouts with a lower sequence have been claimed
+    pub season_id: u64,  // Season of the most recent payout registered in one
+    pub season_pending: u64,  // Part of total_pending registered in season_id
+}
+impl PayoutRegistry {
+    pub const SIZE: usize = 32 + 8 + 8 + 4 + 8 + 4 + 8 + 8;  // 80 bytes
+
+    /// Adds a payout registered in `season_id` to the season's pending amount,
+    /// starting over when it is a newer season than the last
+    pub fn credit_season(&mut self, season_id: u64, amount: u64) -> Result<()> {
+        if season_id == 0 {
+            return Ok(());
+        }
+        if season_id != self.season_id {
+            self.season_id = season_id;
+            self.season_pending = 0;
+        }
+        self.season_pending = self.season_pending.checked_add(amount).ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+
+    /// Replaces a pending payout's amount in its season's pending amount, if still tracked
+    pub fn amend_season(&mut self, season_id: u64, old_amount: u64, new_amount: u64) -> Result<()> {
+        if season_id != 0 && season_id == self.season_id {
+            self.season_pending = self.season_pending
+                .checked_sub(old_amount)
+                .and_then(|pending| pending.checked_add(new_amount))
+                .ok_or(VaultError::Overflow)?;
+        }
+        Ok(())
+    }
+
+    /// Clears the season's pending amount once everything pending is claimed,
+    /// returning the part registered in the active season
+    pub fn take_season_pending(&mut self, active_season_id: u64) -> u64 {
+        let amount = if self.season_id != 0 && self.season_id == active_season_id {
+            self.season_pending
+        } else {
+            0
+        };
+        self.season_pending = 0;
+        amount
+    }
+}
+
+/// Points ledger for one racer, either lifetime (`racer_stats` seeds) or for one
+/// season (`season_stats` seeds); payouts in every mint count towards it
+#[account]
+pub struct RacerStats {
+    pub racer: Pubkey,
+    pub season_id: u64,  // 0 for lifetime stats
+    pub total_points: u64,
+    pub race_count: u32,
+    pub best_finish: u32,  // Best 1-based finishing place from settle_race; 0 = none recorded
+    pub last_race_at: i64,
+}
+impl RacerStats {
+    pub const SIZE: usize = 32 + 8 + 8 + 4 + 4 + 8;  // 64 bytes
+
+    /// Adds one race result; `place` is 1-based, or 0 when the place is unknown
+    pub fn record_race(&mut self, racer: Pubkey, points: u64, place: u32, now: i64) -> Result<()> {
+        if self.racer == Pubkey::default() {
+            self.racer = racer;
+        }
+        self.total_points = self.total_points.checked_add(points).ok_or(VaultError::Overflow)?;
+        self.race_count = self.race_count.checked_add(1).ok_or(VaultError::Overflow)?;
+        if place > 0 && (self.best_finish == 0 || place < self.best_finish) {
+            self.best_finish = place;
+        }
+        self.last_race_at = now;
+        Ok(())
+    }
+
+    /// Replaces the points of a recorded race result; the best finish is kept
+    pub fn amend_points(&mut self, old_points: u64, new_points: u64) -> Result<()> {
+        self.total_points = self.total_points
+            .checked_sub(old_points)
+            .and_then(|total| total.checked_add(new_points))
+            .ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+
+    /// Takes back a cancelled race result; the best finish is kept
+    pub fn remove_race(&mut self, points: u64) -> Result<()> {
+        self.amend_points(points, 0)?;
+        self.race_count = self.race_count.checked_sub(1).ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+}
+
+#[account]
+pub struct GlobalPayoutRegistry {
+    pub total_pending: u64,
+    pub total_claimed: u64,
+    pub total_payout_count: u32,
+    pub total_recipient_count: u32,
+    pub last_updated: i64,
+}
+impl GlobalPayoutRegistry {
+    pub const SIZE: usize = 8 + 8 + 4 + 4 + 8;  // 32 bytes
+
+    /// Adds newly registered payouts, `new_recipients` of which are recipients' first
+    pub fn record_payouts(&mut self, amount: u64, payout_count: u32, new_recipients: u32, now: i64) -> Result<()> {
+        self.total_pending = self.total_pending.checked_add(amount).ok_or(VaultError::Overflow)?;
+        self.total_payout_count = self.total_payout_count.checked_add(payout_count).ok_or(VaultError::Overflow)?;
+        self.total_recipient_count = self.total_recipient_count.checked_add(new_recipients).ok_or(VaultError::Overflow)?;
+        self.last_updated = now;
+        Ok(())
+    }
+}
+
+/// Entry fees received for one mint whose races are not settled yet
+#[account]
+pub struct GlobalEntryFeeRegistry {
+    pub total_held: u64,  // Sum of Race::entry_fees_held over the mint's races; reserved like pending payouts
+    pub total_collected: u64,
+    pub last_updated: i64,
+}
+impl GlobalEntryFeeRegistry {
+    pub const SIZE: usize = 8 + 8 + 8;  // 24 bytes
+}
+
+#[account]
+pub struct GlobalReferralRegistry {
+    pub total_pending: u64,
+    pub total_claimed: u64,
+    pub total_bonus_count: u32,
+    pub total_referrer_count: u32,
+    pub last_updated: i64,
+}
+impl GlobalReferralRegistry {
+    pub const SIZE: usize = 8 + 8 + 4 + 4 + 8;  // 32 bytes
+}
+
+#[account]
+pub struct Race {
+    pub race_id_hash: [u8; 32],
+    pub mint: Pubkey,  // Denomination of the prize budget (SOL_DENOMINATION for lamports)
+    pub status: RaceStatus,
+    pub prize_budget: u64,
+    pub settled_total: u64,  // Payouts registered against the race; never above prize_budget
+    pub finisher_count: u32,  // Payouts (or distribution leaves) registered against the race
+    pub created_at: i64,
+    pub started_at: i64,  // 0 until started
+    pub ended_at: i64,  // 0 until settled or cancelled
+    pub entry_fee: u64,  // Charged by enter_race; 0 = free entry
+    pub entrant_count: u32,  // Outstanding entry tickets
+    pub entry_fees_collected: u64,  // Net entry fees received, included in prize_budget
+    pub prize_table: Pubkey,  // PrizeTable used by settle_race; Pubkey::default() = none
+}
+impl Race {
+    pub const SIZE: usize = 32 + 32 + 1 + 8 + 8 + 4 + 8 + 8 + 8 + 8 + 4 + 8 + 32;  // 161 bytes
+
+    /// Entry fees the vault still owes to this race's entrants or payouts
+    /// Payouts draw on the fees first; a settled race's leftover fees are surplus
+    pub fn entry_fees_held(&self) -> u64 {
+        match self.status {
+            RaceStatus::Open | RaceStatus::Running | RaceStatus::Cancelled => {
+                self.entry_fees_collected.saturating_sub(self.settled_total)
+            }
+            RaceStatus::Settled => 0,
+        }
+    }
+
+    /// Adds registered payouts to the race total, failing past the prize budget
+    pub fn record_payouts(&mut self, amount: u64, finishers: u32) -> Result<()> {
+        let settled_total = self.settled_total.checked_add(amount).ok_or(VaultError::Overflow)?;
+        require!(settled_total <= self.prize_budget, VaultError::RaceBudgetExceeded);
+        self.settled_total = settled_total;
+        self.finisher_count = self.finisher_count.checked_add(finishers).ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+}
+
+#[account]
+pub struct Season {
+    pub season_id: u64,
+    pub mint: Pubkey,  // Denomination of the budget (SOL_DENOMINATION for lamports)
+    pub status: SeasonStatus,
+    pub start_at: i64,
+    pub end_at: i64,  // Registrations are accepted while start_at <= now < end_at
+    pub budget: u64,
+    pub total_registered: u64,  // Payouts in `mint` registered during the season; never above budget
+    pub total_claimed: u64,  // Payouts in `mint` registered during the season and claimed while it was open
+    pub payout_count: u32,  // Payouts registered during the season, in any mint
+    pub closed_at: i64,  // 0 until closed
+}
+impl Season {
+    pub const SIZE: usize = 8 + 32 + 1 + 8 + 8 + 8 + 8 + 8 + 4 + 8;  // 93 bytes
+
+    /// Adds registered payouts to the season, failing past its budget
+    /// Registrations outside its window count towards no season; payouts in
+    /// other mints are counted but not budgeted
+    pub fn record_registration(&mut self, mint: &Pubkey, amount: u64, count: u32, now: i64) -> Result<()> {
+        require!(self.status == SeasonStatus::Open, VaultError::SeasonNotActive);
+        if !self.is_registering(now) {
+            return Ok(());
+        }
+        if *mint == self.mint {
+            let total_registered = self.total_registered.checked_add(amount).ok_or(VaultError::Overflow)?;
+            require!(total_registered <= self.budget, VaultError::SeasonBudgetExceeded);
+            self.total_registered = total_registered;
+        }
+        self.payout_count = self.payout_count.checked_add(count).ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+
+    /// Whether registrations at `now` count towards the season
+    pub fn is_registering(&self, now: i64) -> bool {
+        self.status == SeasonStatus::Open && now >= self.start_at && now < self.end_at
+    }
+
+    /// Replaces a registered payout's amount while the season is open, failing past its budget
+    pub fn amend_registration(&mut self, mint: &Pubkey, old_amount: u64, new_amount: u64) -> Result<()> {
+        if self.status == SeasonStatus::Open && *mint == self.mint {
+            let total_registered = self.total_registered
+                .checked_sub(old_amount)
+                .and_then(|total| total.checked_add(new_amount))
+                .ok_or(VaultError::Overflow)?;
+            require!(total_registered <= self.budget, VaultError::SeasonBudgetExceeded);
+            self.total_registered = total_registered;
+        }
+        Ok(())
+    }
+
+    /// Removes cancelled payouts while the season is open, releasing their budget
+    pub fn release_registration(&mut self, mint: &Pubkey, amount: u64, count: u32) -> Result<()> {
+        if self.status == SeasonStatus::Open {
+            self.amend_registration(mint, amount, 0)?;
+            self.payout_count = self.payout_count.checked_sub(count).ok_or(VaultError::Overflow)?;
+        }
+        Ok(())
+    }
+
+    /// Adds claimed payouts in the season's mint to its claimed total
+    pub fn record_claim(&mut self, mint: &Pubkey, amount: u64) -> Result<()> {
+        if self.status == SeasonStatus::Open && *mint == self.mint {
+            self.total_claimed = self.total_claimed.checked_add(amount).ok_or(VaultError::Overflow)?;
+        }
+        Ok(())
+    }
+}
+
+/// Top LEADERBOARD_SIZE racers of a season by season points, highest first
+/// Ties keep the racer who reached the score first ahead
+#[account(zero_copy)]
+pub struct Leaderboard {
+    pub season_id: u64,
+    pub len: u32,  // Filled entries at the front of `entries`
+    pub _padding: [u8; 4],
+    pub entries: [LeaderboardEntry; LEADERBOARD_SIZE],
+}
+impl Leaderboard {
+    pub const SIZE: usize = 8 + 4 + 4 + LeaderboardEntry::SIZE * LEADERBOARD_SIZE;  // 4016 bytes
+
+    /// Sets the racer's season points and restores the order
+    /// A racer not on a full board replaces the last entry if now ahead of it, and
+    /// one dropping to 0 points leaves the board. Racers off the board are not
+    /// tracked, so a lowered entry keeps its place until someone overtakes it
+    pub fn record(&mut self, racer: Pubkey, points: u64) {
+        let len = self.len as usize;
+        let mut index = match self.entries[..len].iter().position(|entry| entry.racer == racer) {
+            Some(index) => index,
+            None if points == 0 => return,
+            None if len < LEADERBOARD_SIZE => {
+                self.len += 1;
+                len
+            }
+            None if points > self.entries[len - 1].points => len - 1,
+            None => return,
+        };
+        if points == 0 {
+            self.entries.copy_within(index + 1..len, index);
+            self.entries[len - 1] = LeaderboardEntry { racer: Pubkey::default(), points: 0 };
+            self.len -= 1;
+            return;
+        }
+        self.entries[index] = LeaderboardEntry { racer, points };
+        while index > 0 && self.entries[index - 1].points < points {
+            self.entries.swap(index - 1, index);
+            index -= 1;
+        }
+        // A lowered racer falls behind everyone now level with or ahead of it
+        while index + 1 < self.len as usize && self.entries[index + 1].points >= points {
+            self.entries.swap(index, index + 1);
+            index += 1;
+        }
+    }
+}
+
+#[zero_copy]
+pub struct LeaderboardEntry {
+    pub racer: Pubkey,
+    pub points: u64,
+}
+impl LeaderboardEntry {
+    pub const SIZE: usize = 32 + 8;  // 40 bytes
+}
+
+// Pod forbids implicit padding; keep the declared sizes in step with the layout
+const _: () = assert!(std::mem::size_of::<LeaderboardEntry>() == LeaderboardEntry::SIZE);
+const _: () = assert!(std::mem::size_of::<Leaderboard>() == Leaderboard::SIZE);
 
-    /// Global payout registry (tracks all payouts)
-    #[account(
-        seeds = [b"global_payout_registry", config.key().as_ref()],
-        bump
-    )]
-    pub global_payout_registry: Account<'info, GlobalPayoutRegistry>,
+/// Open -> Closed; a closed season's totals no longer change
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SeasonStatus {
+    Open,
+    Closed,
 }
 
 #[account]
-pub struct Config {
-    pub authority: Pubkey,
-    pub mint: Pubkey,
-    pub vault_signer_bump: u8,
-    pub paused: bool,
+pub struct EntryTicket {
+    pub race_id_hash: [u8; 32],
+    pub racer: Pubkey,
+    pub amount: u64,  // Entry fee received by the vault, refunded if the race is cancelled
+    pub entered_at: i64,
 }
-impl Config {
-    pub const SIZE: usize = 32 + 32 + 1 + 1;  // 66 bytes
+impl EntryTicket {
+    pub const SIZE: usize = 32 + 32 + 8 + 8;  // 80 bytes
 }
 
-#[account]
-pub struct PayoutReceipt {
-    pub race_id_hash: [u8; 32],  // Hash of the race_id (CUID)
-    pub recipient: Pubkey,
-    pub points: u64,
-    pub amount: u64,
-    pub timestamp: i64,
-}
-impl PayoutReceipt {
-    pub const SIZE: usize = 32 + 32 + 8 + 8 + 8;  // 88 bytes
+/// Open -> Running -> Settled, or Cancelled before any payout is registered
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RaceStatus {
+    Open,
+    Running,
+    Settled,
+    Cancelled,
 }
 
 #[account]
-pub struct ReferralBonus {
-    pub race_id: String,
-    pub referrer: Pubkey,
-    pub referee: Pubkey,
-    pub amount: u64,
-    pub claimed: bool,
-    pub timestamp: i64,
+pub struct RaceTombstone {
+    pub race_id_hash: [u8; 32],
+    pub closed_count: u32,
+    pub closed_at: i64,
 }
-impl ReferralBonus {
-    pub const SIZE: usize = 4 + 32 + 32 + 32 + 8 + 1 + 8;  // 117 bytes (4 for string length)
+impl RaceTombstone {
+    pub const SIZE: usize = 32 + 4 + 8;  // 44 bytes
 }
 
 #[account]
-pub struct ReferrerRegistry {
-    pub referrer: Pubkey,
-    pub total_pending: u64,
-    pub total_claimed: u64,
-    pub bonus_count: u32,
-    pub last_updated: i64,
+pub struct DepositorLedger {
+    pub depositor: Pubkey,
+    pub total_deposited: u64,
+    pub deposit_count: u32,
+    pub first_deposit_at: i64,
+    pub last_deposit_at: i64,
 }
-impl ReferrerRegistry {
-    pub const SIZE: usize = 32 + 8 + 8 + 4 + 8;  // 60 bytes
+impl DepositorLedger {
+    pub const SIZE: usize = 32 + 8 + 4 + 8 + 8;  // 60 bytes
 }
 
 #[account]
-pub struct PayoutRegistry {
+pub struct VoucherClaim {
+    pub race_id_hash: [u8; 32],
     pub recipient: Pubkey,
-    pub total_pending: u64,
-    pub total_claimed: u64,
-    pub payout_count: u32,
-    pub last_updated: i64,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub claimed_at: i64,
 }
-impl PayoutRegistry {
-    pub const SIZE: usize = 32 + 8 + 8 + 4 + 8;  // 60 bytes
+impl VoucherClaim {
+    pub const SIZE: usize = 32 + 32 + 32 + 8 + 8;  // 112 bytes
 }
 
 #[account]
-pub struct GlobalPayoutRegistry {
-    pub total_pending: u64,
-    pub total_claimed: u64,
-    pub total_payout_count: u32,
-    pub total_recipient_count: u32,
-    pub last_updated: i64,
+pub struct RaceDistribution {
+    pub race_id_hash: [u8; 32],
+    pub merkle_root: [u8; 32],
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub leaf_count: u32,
+    pub claimed_count: u32,
+    pub created_at: i64,
+    pub season_id: u64,  // Season the distribution counts towards; 0 = none
+    pub points_rate: u64,  // Leaf amounts must be worth their points at this rate; 0 = as posted
+    pub expires_at: i64,  // Unclaimed leaves can be released from this time; 0 = never expires
+    pub claimed_bitmap: Vec<u8>,  // One bit per leaf index
 }
-impl GlobalPayoutRegistry {
-    pub const SIZE: usize = 8 + 8 + 4 + 4 + 8;  // 32 bytes
+impl RaceDistribution {
+    pub fn bitmap_len(leaf_count: u32) -> usize {
+        (leaf_count as usize).div_ceil(8)
+    }
+
+    pub fn space(leaf_count: u32) -> usize {
+        32 + 32 + 8 + 8 + 4 + 4 + 8 + 8 + 8 + 8 + 4 + Self::bitmap_len(leaf_count)
+    }
+
+    pub fn is_claimed(&self, index: u32) -> bool {
+        self.claimed_bitmap[index as usize / 8] & (1 << (index % 8)) != 0
+    }
+
+    pub fn set_claimed(&mut self, index: u32) {
+        self.claimed_bitmap[index as usize / 8] |= 1 << (index % 8);
+    }
+}
+
+#[event]
+pub struct MigrationEvent {
+    pub authority: Pubkey,
+    pub config_size: u32,
+    pub referrer_count: u32,
+    pub referral_pending: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositEvent {
+    pub depositor: Pubkey,
+    pub amount: u64,  // Credited to the vault, net of transfer_fee
+    pub transfer_fee: u64,
+    pub vault_balance: u64,
+    pub total_deposited: u64,
+    pub memo: Option<String>,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayoutsMigratedEvent {
+    pub recipient: Pubkey,
+    pub receipt_count: u32,
+    pub claimed_count: u32,
+    pub pending_total: u64,
+    pub timestamp: i64,
 }
 
+#[event]
+pub struct ReferralBonusesMigratedEvent {
+    pub referrer: Pubkey,
+    pub bonus_count: u32,  // Legacy bonuses migrated
+    pub claimed_count: u32,  // Of those, paid by a legacy claim
+    pub pending_total: u64,
+    pub timestamp: i64,
+}
 
 #[event]
 pub struct ReconcileEvent {
@@ -984,21 +6716,95 @@ pub struct ReconcileEvent {
 }
 
 #[event]
-pub struct AuthorityTransferEvent {
+pub struct SurplusWithdrawnEvent {
+    pub treasurer: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub reserved: u64,
+    pub remaining_balance: u64,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferProposedEvent {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferAcceptedEvent {
     pub old_authority: Pubkey,
     pub new_authority: Pubkey,
     pub timestamp: i64,
 }
 
+#[event]
+pub struct AuthorityTransferCancelledEvent {
+    pub authority: Pubkey,
+    pub cancelled_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoleGrantedEvent {
+    pub role: Role,
+    pub holder: Pubkey,
+    pub previous_holder: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoleRevokedEvent {
+    pub role: Role,
+    pub previous_holder: Pubkey,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct ConfigUpdateEvent {
     pub paused: bool,
+    pub reserve_ratio_bps: u16,
+    pub claim_window: i64,
+    pub receipt_close_horizon: i64,
+    pub voucher_signer: Pubkey,
+    pub points_rate: u64,
+    pub amounts_from_points: bool,
+    pub season_id: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PointsRateUpdatedEvent {
+    pub old_rate: u64,
+    pub new_rate: u64,
+    pub rate_decimals: u8,  // Rates are whole tokens per point scaled by 10^rate_decimals
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultDecommissionedEvent {
+    pub authority: Pubkey,
+    pub treasury_token: Pubkey,
+    pub amount_swept: u64,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct ConfigCloseEvent {
+pub struct MintAddedEvent {
     pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub vault_token: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintRemovedEvent {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub treasury_token: Pubkey,
+    pub amount_swept: u64,
     pub timestamp: i64,
 }
 
@@ -1012,11 +6818,97 @@ pub struct ReferralBonusRegisteredEvent {
 }
 
 
+#[event]
+pub struct ReferralBonusClaimedEvent {
+    pub bonus: Pubkey,
+    pub race_id: String,
+    pub referrer: Pubkey,
+    pub referee: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct PendingBonusesClaimedEvent {
     pub referrer: Pubkey,
     pub amount: u64,
-    pub bonus_count: u32,
+    pub transfer_fee: u64,
+    pub bonus_count: u32,  // Bonuses settled by this claim
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PrizeTableCreatedEvent {
+    pub table_id: u64,
+    pub mode: PrizeMode,
+    pub place_shares_bps: Vec<u16>,
+    pub points_rate: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RaceCreatedEvent {
+    pub race_id: String,
+    pub race_id_hash: [u8; 32],
+    pub prize_budget: u64,
+    pub entry_fee: u64,
+    pub prize_table: Pubkey,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RaceEnteredEvent {
+    pub race_id_hash: [u8; 32],
+    pub racer: Pubkey,
+    pub amount: u64,  // Credited to the prize budget, net of transfer_fee
+    pub transfer_fee: u64,
+    pub prize_budget: u64,
+    pub entrant_count: u32,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EntryRefundedEvent {
+    pub race_id_hash: [u8; 32],
+    pub racer: Pubkey,
+    pub amount: u64,  // Leaves the vault; the racer receives this minus transfer_fee
+    pub transfer_fee: u64,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RaceStatusChangedEvent {
+    pub race_id_hash: [u8; 32],
+    pub status: RaceStatus,
+    pub settled_total: u64,
+    pub finisher_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SeasonOpenedEvent {
+    pub season_id: u64,
+    pub mint: Pubkey,
+    pub start_at: i64,
+    pub end_at: i64,
+    pub budget: u64,
+    pub timestamp: i64,
+}
+
+/// Final season totals, for season-end rewards
+#[event]
+pub struct SeasonClosedEvent {
+    pub season_id: u64,
+    pub mint: Pubkey,
+    pub start_at: i64,
+    pub end_at: i64,
+    pub budget: u64,
+    pub total_registered: u64,
+    pub total_claimed: u64,
+    pub payout_count: u32,
     pub timestamp: i64,
 }
 
@@ -1027,14 +6919,113 @@ pub struct PayoutRegisteredEvent {
     pub recipient: Pubkey,
     pub points: u64,
     pub amount: u64,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RacePayoutsRegisteredEvent {
+    pub race_id: String,
+    pub race_id_hash: [u8; 32],
+    pub recipient_count: u32,
+    pub total_points: u64,
+    pub total_amount: u64,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RaceDistributionPostedEvent {
+    pub race_id: String,
+    pub race_id_hash: [u8; 32],
+    pub merkle_root: [u8; 32],
+    pub total_amount: u64,
+    pub leaf_count: u32,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DistributionClaimedEvent {
+    pub race_id_hash: [u8; 32],
+    pub index: u32,
+    pub recipient: Pubkey,
+    pub points: u64,
+    pub amount: u64,
+    pub transfer_fee: u64,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RaceDistributionClosedEvent {
+    pub race_id_hash: [u8; 32],
+    pub released_amount: u64,
+    pub released_count: u32,
+    pub claimed_count: u32,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayoutCancelledEvent {
+    pub race_id_hash: [u8; 32],
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayoutAmendedEvent {
+    pub race_id_hash: [u8; 32],
+    pub recipient: Pubkey,
+    pub old_points: u64,
+    pub new_points: u64,
+    pub old_amount: u64,
+    pub new_amount: u64,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayoutExpiredEvent {
+    pub race_id_hash: [u8; 32],
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub expired_at: i64,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReceiptsClosedEvent {
+    pub race_id_hash: [u8; 32],
+    pub closed_count: u32,
+    pub lamports_reclaimed: u64,
+    pub mint: Pubkey,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct PayoutsClaimedEvent {
     pub recipient: Pubkey,
-    pub total_amount: u64,
+    pub total_amount: u64,  // Leaves the vault; the recipient receives this minus transfer_fee
+    pub transfer_fee: u64,
     pub payout_count: u32,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VoucherClaimedEvent {
+    pub race_id_hash: [u8; 32],
+    pub recipient: Pubkey,
+    pub points: u64,
+    pub amount: u64,  // Leaves the vault; the recipient receives this minus transfer_fee
+    pub transfer_fee: u64,
+    pub nonce: u64,
+    pub mint: Pubkey,
     pub timestamp: i64,
 }
 
@@ -1063,4 +7054,124 @@ pub enum VaultError {
     RaceIdTooLong,
     #[msg("Self-referrals are not allowed")]
     SelfReferralNotAllowed,
+    #[msg("Invalid authority")]
+    InvalidAuthority,
+    #[msg("No pending authority transfer")]
+    NoPendingAuthority,
+    #[msg("Signer does not hold the required role")]
+    Unauthorized,
+    #[msg("Invalid role holder")]
+    InvalidRoleHolder,
+    #[msg("Role is not assigned")]
+    RoleNotAssigned,
+    #[msg("Amount exceeds unreserved vault surplus")]
+    InsufficientSurplus,
+    #[msg("Outstanding obligations would exceed the vault balance")]
+    Overcommitted,
+    #[msg("Reserve ratio must be at most 10000 basis points")]
+    InvalidReserveRatio,
+    #[msg("Invalid migration account")]
+    InvalidMigrationAccount,
+    #[msg("Invalid referral bonus account")]
+    InvalidReferralBonus,
+    #[msg("Referral bonus already claimed")]
+    BonusAlreadyClaimed,
+    #[msg("Payout batch is empty")]
+    EmptyPayoutBatch,
+    #[msg("Payout batch exceeds maximum size")]
+    PayoutBatchTooLarge,
+    #[msg("Remaining accounts do not match the expected accounts")]
+    InvalidRemainingAccounts,
+    #[msg("Leaf count must be between 1 and the maximum distribution size")]
+    InvalidLeafCount,
+    #[msg("Invalid merkle proof")]
+    InvalidMerkleProof,
+    #[msg("Distribution leaf already claimed")]
+    DistributionAlreadyClaimed,
+    #[msg("Claim exceeds the distribution total")]
+    DistributionExhausted,
+    #[msg("Payout has already been claimed")]
+    PayoutAlreadyClaimed,
+    #[msg("Payout has been cancelled")]
+    PayoutCancelled,
+    #[msg("Memo exceeds maximum length of 64 bytes")]
+    MemoTooLong,
+    #[msg("Payout has expired")]
+    PayoutExpired,
+    #[msg("Payout has not expired")]
+    PayoutNotExpired,
+    #[msg("Claim window must not be negative")]
+    InvalidClaimWindow,
+    #[msg("Receipt close horizon must not be negative")]
+    InvalidCloseHorizon,
+    #[msg("Receipt closing is disabled")]
+    ReceiptClosingDisabled,
+    #[msg("Receipt is younger than the close horizon")]
+    ReceiptTooRecent,
+    #[msg("Payout is still pending")]
+    PayoutNotSettled,
+    #[msg("Receipts for this race have been closed")]
+    RaceReceiptsClosed,
+    #[msg("Pending payouts or bonuses are still outstanding")]
+    OutstandingObligations,
+    #[msg("Treasury account cannot be the vault token account")]
+    InvalidTreasury,
+    #[msg("Mint is not accepted by this vault")]
+    MintNotAccepted,
+    #[msg("Mint is already accepted by this vault")]
+    MintAlreadyAccepted,
+    #[msg("Vault accepts the maximum number of mints")]
+    TooManyMints,
+    #[msg("Secondary mints must be removed first")]
+    MintsStillAccepted,
+    #[msg("Voucher claims are disabled")]
+    VouchersDisabled,
+    #[msg("Voucher has expired")]
+    VoucherExpired,
+    #[msg("Missing ed25519 signature instruction for the voucher")]
+    MissingVoucherSignature,
+    #[msg("Voucher signature does not match the voucher")]
+    InvalidVoucherSignature,
+    #[msg("Race is not running")]
+    RaceNotRunning,
+    #[msg("Race is denominated in a different mint")]
+    RaceMintMismatch,
+    #[msg("Race payouts would exceed the prize budget")]
+    RaceBudgetExceeded,
+    #[msg("Race cannot move to that status")]
+    InvalidRaceTransition,
+    #[msg("Race has registered payouts")]
+    RaceHasPayouts,
+    #[msg("Race is not open for entries")]
+    RaceNotOpen,
+    #[msg("Race has not been cancelled")]
+    RaceNotCancelled,
+    #[msg("Invalid prize table")]
+    InvalidPrizeTable,
+    #[msg("Prize table does not belong to this race")]
+    PrizeTableMismatch,
+    #[msg("Deriving amounts from points requires a non-zero points rate")]
+    InvalidPointsRate,
+    #[msg("Amount does not match the amount derived from points")]
+    PointsAmountMismatch,
+    #[msg("Season is not open for registrations")]
+    SeasonNotActive,
+    #[msg("Payout would exceed the season budget")]
+    SeasonBudgetExceeded,
+    #[msg("Another season is already active")]
+    SeasonAlreadyActive,
+    #[msg("The active season's accounts must be provided")]
+    SeasonAccountMissing,
+    #[msg("Invalid season id or window")]
+    InvalidSeason,
+    #[msg("Entry fees are only supported for token races")]
+    EntryFeeRequiresToken,
+    #[msg("Race payouts come from its prize table via settle_race")]
+    RaceUsesPrizeTable,
+    #[msg("Race account must be provided exactly when the payout has a race")]
+    RaceAccountMismatch,
+    #[msg("The payout's points ledgers must be provided")]
+    StatsAccountMissing,
+    #[msg("Distribution has unclaimed leaves and has not expired")]
+    DistributionNotExpired,
 }
@@ -0,0 +1,113 @@
+//! MAX_BATCH_PAYOUTS must leave register_race_payouts and settle_race transactions
+//! within the packet size once their accounts are in an address lookup table
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    address_lookup_table::AddressLookupTableAccount,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use vault_program::{Finisher, PayoutEntry, BATCH_ENTRY_ACCOUNTS, MAX_BATCH_PAYOUTS};
+
+const PACKET_DATA_SIZE: usize = 1232;  // Largest serialized transaction the network accepts
+const COMPUTE_BUDGET_ID: Pubkey = pubkey!("ComputeBudget111111111111111111111111111111");
+const RACE_ID: &str = "clx0race0000000000000000a";  // Race ids are 25-char CUIDs
+
+/// Serialized size of a single-signer v0 transaction carrying `ix` after a compute
+/// unit limit, with every account except the signer in one lookup table
+fn transaction_size(ix: Instruction) -> usize {
+    let payer = ix.accounts.iter().find(|meta| meta.is_signer).unwrap().pubkey;
+    let table = AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: ix.accounts.iter().map(|meta| meta.pubkey).filter(|key| *key != payer).collect(),
+    };
+    let mut limit_data = vec![2u8];  // SetComputeUnitLimit
+    limit_data.extend_from_slice(&1_400_000u32.to_le_bytes());
+    let compute_limit = Instruction { program_id: COMPUTE_BUDGET_ID, accounts: vec![], data: limit_data };
+
+    let message = v0::Message::try_compile(&payer, &[compute_limit, ix], &[table], Hash::default()).unwrap();
+    1 + 64 + VersionedMessage::V0(message).serialize().len()
+}
+
+/// Account metas for `entries` batch entries after the instruction's own accounts
+fn with_entry_accounts(accounts: impl ToAccountMetas, entries: usize) -> Vec<AccountMeta> {
+    let mut metas = accounts.to_account_metas(None);
+    metas.extend((0..entries * BATCH_ENTRY_ACCOUNTS).map(|_| AccountMeta::new(Pubkey::new_unique(), false)));
+    metas
+}
+
+fn register_race_payouts(entries: usize) -> Instruction {
+    let accounts = vault_program::accounts::RegisterRacePayouts {
+        config: Pubkey::new_unique(),
+        operator: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        race: Pubkey::new_unique(),
+        race_tombstone: Pubkey::new_unique(),
+        global_payout_registry: Pubkey::new_unique(),
+        season: Some(Pubkey::new_unique()),
+        leaderboard: Some(Pubkey::new_unique()),
+        global_referral_registry: Pubkey::new_unique(),
+        global_entry_fee_registry: Pubkey::new_unique(),
+        vault_signer: Pubkey::new_unique(),
+        vault_token: Pubkey::new_unique(),
+        system_program: Pubkey::new_unique(),
+        token_program: Pubkey::new_unique(),
+    };
+    let data = vault_program::instruction::RegisterRacePayouts {
+        race_id: RACE_ID.to_string(),
+        race_id_hash: [0; 32],
+        entries: (0..entries)
+            .map(|_| PayoutEntry { recipient: Pubkey::new_unique(), points: u64::MAX, amount: u64::MAX })
+            .collect(),
+    };
+    Instruction {
+        program_id: vault_program::ID,
+        accounts: with_entry_accounts(accounts, entries),
+        data: data.data(),
+    }
+}
+
+fn settle_race(finishers: usize) -> Instruction {
+    let accounts = vault_program::accounts::SettleRace {
+        config: Pubkey::new_unique(),
+        operator: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        race: Pubkey::new_unique(),
+        prize_table: Pubkey::new_unique(),
+        race_tombstone: Pubkey::new_unique(),
+        global_payout_registry: Pubkey::new_unique(),
+        season: Some(Pubkey::new_unique()),
+        leaderboard: Some(Pubkey::new_unique()),
+        global_referral_registry: Pubkey::new_unique(),
+        global_entry_fee_registry: Pubkey::new_unique(),
+        vault_signer: Pubkey::new_unique(),
+        vault_token: Pubkey::new_unique(),
+        system_program: Pubkey::new_unique(),
+        token_program: Pubkey::new_unique(),
+    };
+    let data = vault_program::instruction::SettleRace {
+        race_id: RACE_ID.to_string(),
+        race_id_hash: [0; 32],
+        finishers: (0..finishers)
+            .map(|_| Finisher { recipient: Pubkey::new_unique(), points: u64::MAX })
+            .collect(),
+    };
+    Instruction {
+        program_id: vault_program::ID,
+        accounts: with_entry_accounts(accounts, finishers),
+        data: data.data(),
+    }
+}
+
+#[test]
+fn largest_register_race_payouts_batch_fits_a_transaction() {
+    assert!(transaction_size(register_race_payouts(MAX_BATCH_PAYOUTS)) <= PACKET_DATA_SIZE);
+    assert!(transaction_size(register_race_payouts(MAX_BATCH_PAYOUTS + 1)) > PACKET_DATA_SIZE);
+}
+
+#[test]
+fn largest_settle_race_batch_fits_a_transaction() {
+    assert!(transaction_size(settle_race(MAX_BATCH_PAYOUTS)) <= PACKET_DATA_SIZE);
+}
@@ -0,0 +1,550 @@
+//! In-process runtime shared by the integration tests
+//!
+//! Accounts are serialized the way the runtime hands them to a program, so
+//! account creation, reallocation and closing behave as on chain. The program's
+//! CPIs go to the Token-2022 processor or a minimal system program through the
+//! syscall stubs; signatures are not checked
+
+#![allow(dead_code)]
+
+use std::cell::Cell;
+use std::collections::HashMap;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    account_info::MAX_PERMITTED_DATA_INCREASE,
+    ed25519_program,
+    entrypoint::{deserialize, ProgramResult},
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    sysvar,
+};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        transfer_fee::{instruction::initialize_transfer_fee_config, TransferFeeAmount},
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+    instruction::{initialize_account3, initialize_mint2, mint_to},
+    processor::Processor,
+    state,
+};
+use vault_program::{Config, VaultError};
+
+pub const DECIMALS: u8 = 6;
+pub const NOW: i64 = 1_700_000_000;
+
+thread_local! {
+    static CLOCK: Cell<i64> = const { Cell::new(NOW) };
+}
+
+/// Moves the clock every instruction on this thread reads
+pub fn warp(unix_timestamp: i64) {
+    CLOCK.with(|clock| clock.set(unix_timestamp));
+}
+
+/// Sends the program's CPIs to Token-2022 or the system program and serves the
+/// sysvars it reads
+struct Runtime;
+
+impl SyscallStubs for Runtime {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let accounts = instruction.accounts.iter()
+            .map(|meta| {
+                let mut info = account_infos.iter().find(|info| *info.key == meta.pubkey).unwrap().clone();
+                info.is_signer |= meta.is_signer;
+                info
+            })
+            .collect::<Vec<_>>();
+        if instruction.program_id == spl_token_2022::ID {
+            Processor::process(&instruction.program_id, &accounts, &instruction.data)
+        } else if instruction.program_id == system_program::ID {
+            process_system(&accounts, &instruction.data)
+        } else {
+            panic!("unexpected CPI to {}", instruction.program_id)
+        }
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock { unix_timestamp: CLOCK.with(Cell::get), ..Clock::default() };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+}
+
+/// The system instructions anchor's account constraints use: CreateAccount,
+/// Assign, Transfer and Allocate, with the system program's in-use checks
+fn process_system(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    const ACCOUNT_ALREADY_IN_USE: ProgramError = ProgramError::Custom(0);
+    let u64_at = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+    let pubkey_at = |at: usize| Pubkey::try_from(&data[at..at + 32]).unwrap();
+    let unused = |account: &AccountInfo| account.data_is_empty() && *account.owner == system_program::ID;
+    match u32::from_le_bytes(data[..4].try_into().unwrap()) {
+        0 => {
+            if accounts[1].lamports() > 0 || !unused(&accounts[1]) {
+                return Err(ACCOUNT_ALREADY_IN_USE);
+            }
+            move_lamports(&accounts[0], &accounts[1], u64_at(4))?;
+            accounts[1].resize(u64_at(12) as usize)?;
+            accounts[1].assign(&pubkey_at(20));
+        }
+        1 => accounts[0].assign(&pubkey_at(4)),
+        2 => move_lamports(&accounts[0], &accounts[1], u64_at(4))?,
+        8 => {
+            if !unused(&accounts[0]) {
+                return Err(ACCOUNT_ALREADY_IN_USE);
+            }
+            accounts[0].resize(u64_at(4) as usize)?;
+        }
+        other => panic!("unexpected system instruction {other}"),
+    }
+    Ok(())
+}
+
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    let mut from_lamports = from.try_borrow_mut_lamports()?;
+    **from_lamports = from_lamports.checked_sub(lamports).ok_or(ProgramError::InsufficientFunds)?;
+    let mut to_lamports = to.try_borrow_mut_lamports()?;
+    **to_lamports = to_lamports.checked_add(lamports).ok_or(ProgramError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+pub struct TestAccount {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+/// Accounts by address; instructions for the vault program or Token-2022 run against them
+pub struct Bank {
+    pub accounts: HashMap<Pubkey, TestAccount>,
+}
+
+impl Bank {
+    pub fn new() -> Self {
+        set_syscall_stubs(Box::new(Runtime));
+        warp(NOW);
+        let mut bank = Bank { accounts: HashMap::new() };
+        let programs = [
+            vault_program::ID,
+            system_program::ID,
+            spl_token_2022::ID,
+            anchor_spl::associated_token::ID,
+        ];
+        for program in programs {
+            bank.accounts.insert(program, TestAccount {
+                lamports: 1,
+                data: Vec::new(),
+                owner: Pubkey::default(),
+                executable: true,
+            });
+        }
+        let rent = Rent::default();
+        let mut rent_data = rent.lamports_per_byte_year.to_le_bytes().to_vec();
+        rent_data.extend_from_slice(&rent.exemption_threshold.to_le_bytes());
+        rent_data.push(rent.burn_percent);
+        bank.set(sysvar::rent::ID, sysvar::ID, rent_data);
+        bank
+    }
+
+    pub fn set(&mut self, key: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        self.accounts.insert(key, TestAccount { lamports: 1_000_000_000, data, owner, executable: false });
+    }
+
+    pub fn set_state<T: AccountSerialize>(&mut self, key: Pubkey, state: &T, space: usize) {
+        let mut data = Vec::with_capacity(space);
+        state.try_serialize(&mut data).unwrap();
+        data.resize(space, 0);
+        self.set(key, vault_program::ID, data);
+    }
+
+    pub fn state<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        T::try_deserialize(&mut &self.accounts[key].data[..]).unwrap()
+    }
+
+    /// Whether `key` holds lamports; addresses only ever referenced are empty
+    pub fn exists(&self, key: &Pubkey) -> bool {
+        self.accounts.get(key).is_some_and(|account| account.lamports > 0)
+    }
+
+    /// Token balance and withheld transfer fees of a Token-2022 account
+    pub fn token_balance(&self, key: &Pubkey) -> (u64, u64) {
+        let account = StateWithExtensions::<state::Account>::unpack(&self.accounts[key].data).unwrap();
+        let withheld = account.get_extension::<TransferFeeAmount>().map_or(0, |fee| fee.withheld_amount.into());
+        (account.base.amount, withheld)
+    }
+
+    /// Runs `instruction` as the transaction's only instruction; its account
+    /// changes are kept only if it succeeds, and emptied accounts are removed
+    pub fn process(&mut self, instruction: &Instruction) -> ProgramResult {
+        // Addresses never written to are empty system accounts
+        for meta in &instruction.accounts {
+            self.accounts.entry(meta.pubkey).or_insert(TestAccount {
+                lamports: 0,
+                data: Vec::new(),
+                owner: system_program::ID,
+                executable: false,
+            });
+        }
+
+        let mut input = (instruction.accounts.len() as u64).to_le_bytes().to_vec();
+        for (index, meta) in instruction.accounts.iter().enumerate() {
+            if let Some(first) = instruction.accounts[..index].iter().position(|other| other.pubkey == meta.pubkey) {
+                input.push(first as u8);
+                input.extend_from_slice(&[0; 7]);
+                continue;
+            }
+            let uses = instruction.accounts.iter().filter(|other| other.pubkey == meta.pubkey);
+            let (is_signer, is_writable) = uses.fold((false, false), |(signer, writable), other| {
+                (signer || other.is_signer, writable || other.is_writable)
+            });
+            let account = &self.accounts[&meta.pubkey];
+            input.extend_from_slice(&[u8::MAX, is_signer as u8, is_writable as u8, account.executable as u8]);
+            input.extend_from_slice(&[0; 4]);
+            input.extend_from_slice(meta.pubkey.as_ref());
+            input.extend_from_slice(account.owner.as_ref());
+            input.extend_from_slice(&account.lamports.to_le_bytes());
+            input.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+            input.extend_from_slice(&account.data);
+            input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            input.resize(input.len().next_multiple_of(8), 0);
+            input.extend_from_slice(&u64::MAX.to_le_bytes());
+        }
+        input.extend_from_slice(&(instruction.data.len() as u64).to_le_bytes());
+        input.extend_from_slice(&instruction.data);
+        input.extend_from_slice(instruction.program_id.as_ref());
+
+        // The program reads the input in place, so it has to be 8-byte aligned
+        let mut aligned = vec![0u64; input.len().div_ceil(8)];
+        let buffer = aligned.as_mut_ptr() as *mut u8;
+        unsafe { std::ptr::copy_nonoverlapping(input.as_ptr(), buffer, input.len()) };
+        let (program_id, accounts, data) = unsafe { deserialize(buffer) };
+        if *program_id == vault_program::ID {
+            vault_program::entry(program_id, &accounts, data)?;
+        } else {
+            Processor::process(program_id, &accounts, data)?;
+        }
+
+        for info in &accounts {
+            let Some(account) = self.accounts.get_mut(info.key) else { continue };
+            if info.lamports() == 0 && !account.executable {
+                self.accounts.remove(info.key);
+                continue;
+            }
+            account.lamports = info.lamports();
+            account.data = info.data.borrow().to_vec();
+            account.owner = *info.owner;
+        }
+        Ok(())
+    }
+
+    /// Makes `instructions` the transaction the instructions sysvar describes,
+    /// with the one at `current` executing
+    pub fn set_instructions(&mut self, instructions: &[Instruction], current: u16) {
+        let mut data = (instructions.len() as u16).to_le_bytes().to_vec();
+        let offsets_at = data.len();
+        data.resize(offsets_at + 2 * instructions.len(), 0);
+        for (index, instruction) in instructions.iter().enumerate() {
+            let offset = (data.len() as u16).to_le_bytes();
+            data[offsets_at + 2 * index..offsets_at + 2 * index + 2].copy_from_slice(&offset);
+            data.extend_from_slice(&(instruction.accounts.len() as u16).to_le_bytes());
+            for meta in &instruction.accounts {
+                data.push(meta.is_signer as u8 | (meta.is_writable as u8) << 1);
+                data.extend_from_slice(meta.pubkey.as_ref());
+            }
+            data.extend_from_slice(instruction.program_id.as_ref());
+            data.extend_from_slice(&(instruction.data.len() as u16).to_le_bytes());
+            data.extend_from_slice(&instruction.data);
+        }
+        data.extend_from_slice(&current.to_le_bytes());
+        self.set(sysvar::instructions::ID, sysvar::ID, data);
+    }
+}
+
+/// An ed25519 precompile instruction checking one signature by `signer` over
+/// `message`, laid out the way the program reads it. The program relies on the
+/// runtime to verify the signature itself, so it is left zeroed here
+pub fn ed25519_instruction(signer: &Pubkey, message: &[u8]) -> Instruction {
+    const PUBLIC_KEY_AT: u16 = 16;
+    const SIGNATURE_AT: u16 = PUBLIC_KEY_AT + 32;
+    const MESSAGE_AT: u16 = SIGNATURE_AT + 64;
+    let mut data = vec![1, 0];
+    for field in [SIGNATURE_AT, u16::MAX, PUBLIC_KEY_AT, u16::MAX, MESSAGE_AT, message.len() as u16, u16::MAX] {
+        data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(&[0; 64]);
+    data.extend_from_slice(message);
+    Instruction { program_id: ed25519_program::ID, accounts: Vec::new(), data }
+}
+
+pub fn vault_error(error: VaultError) -> ProgramError {
+    anchor_lang::error::Error::from(error).into()
+}
+
+pub fn anchor_error(error: anchor_lang::error::ErrorCode) -> ProgramError {
+    anchor_lang::error::Error::from(error).into()
+}
+
+/// A vault initialized over a fresh Token-2022 mint; its authority holds every role
+pub struct Vault {
+    pub bank: Bank,
+    pub mint: Pubkey,
+    pub mint_authority: Pubkey,
+    pub transfer_fee: bool,
+    pub config: Pubkey,
+    pub vault_signer: Pubkey,
+    pub vault_token: Pubkey,
+    pub authority: Pubkey,
+}
+
+impl Vault {
+    pub fn new() -> Self {
+        Self::with_mint(None)
+    }
+
+    /// A vault whose primary mint withholds `fee_bps` of every transfer
+    pub fn with_transfer_fee(fee_bps: u16) -> Self {
+        Self::with_mint(Some(fee_bps))
+    }
+
+    fn with_mint(fee_bps: Option<u16>) -> Self {
+        let mut bank = Bank::new();
+        let mint = Pubkey::new_unique();
+        let mint_authority = Pubkey::new_unique();
+        let extensions = fee_bps.map_or(Vec::new(), |_| vec![ExtensionType::TransferFeeConfig]);
+        let mint_len = ExtensionType::try_calculate_account_len::<state::Mint>(&extensions).unwrap();
+        bank.set(mint, spl_token_2022::ID, vec![0; mint_len]);
+        if let Some(fee_bps) = fee_bps {
+            let fee_config = initialize_transfer_fee_config(&spl_token_2022::ID, &mint, None, None, fee_bps, u64::MAX).unwrap();
+            bank.process(&fee_config).unwrap();
+        }
+        bank.process(&initialize_mint2(&spl_token_2022::ID, &mint, &mint_authority, None, DECIMALS).unwrap()).unwrap();
+
+        let config = Pubkey::find_program_address(&[b"config", mint.as_ref()], &vault_program::ID).0;
+        let vault_signer = Pubkey::find_program_address(&[b"vault_signer", config.as_ref()], &vault_program::ID).0;
+        let mut vault = Vault {
+            bank,
+            mint,
+            mint_authority,
+            transfer_fee: fee_bps.is_some(),
+            config,
+            vault_signer,
+            vault_token: get_associated_token_address_with_program_id(&vault_signer, &mint, &spl_token_2022::ID),
+            authority: Pubkey::default(),
+        };
+        vault.authority = vault.wallet();
+        vault.token_account(vault.vault_token, vault_signer, 0);
+
+        let accounts = vault_program::accounts::Initialize {
+            config,
+            vault_signer,
+            mint,
+            vault_token: vault.vault_token,
+            global_payout_registry: vault.global_payout_registry(),
+            global_referral_registry: vault.global_referral_registry(),
+            authority: vault.authority,
+            system_program: system_program::ID,
+            token_program: spl_token_2022::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            rent: sysvar::rent::ID,
+        };
+        vault.send(accounts, vault_program::instruction::Initialize { vault_id: 0 }).unwrap();
+        vault
+    }
+
+    pub fn send(&mut self, accounts: impl ToAccountMetas, data: impl InstructionData) -> ProgramResult {
+        self.bank.process(&self.instruction(accounts, data))
+    }
+
+    pub fn instruction(&self, accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+        Instruction {
+            program_id: vault_program::ID,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        }
+    }
+
+    pub fn pda(&self, seeds: &[&[u8]]) -> Pubkey {
+        Pubkey::find_program_address(seeds, &vault_program::ID).0
+    }
+
+    pub fn ata(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &self.mint, &spl_token_2022::ID)
+    }
+
+    pub fn global_payout_registry(&self) -> Pubkey {
+        self.pda(&[b"global_payout_registry", self.config.as_ref()])
+    }
+
+    pub fn global_referral_registry(&self) -> Pubkey {
+        self.pda(&[b"global_referral_registry", self.config.as_ref()])
+    }
+
+    pub fn global_entry_fee_registry(&self) -> Pubkey {
+        self.pda(&[b"global_entry_fee_registry", self.config.as_ref()])
+    }
+
+    pub fn payout_registry(&self, recipient: &Pubkey) -> Pubkey {
+        self.pda(&[b"payout_registry", self.config.as_ref(), recipient.as_ref()])
+    }
+
+    pub fn receipt(&self, race_id_hash: &[u8; 32], recipient: &Pubkey) -> Pubkey {
+        self.pda(&[b"receipt", self.config.as_ref(), race_id_hash, recipient.as_ref()])
+    }
+
+    pub fn race(&self, race_id_hash: &[u8; 32]) -> Pubkey {
+        self.pda(&[b"race", self.config.as_ref(), race_id_hash])
+    }
+
+    pub fn season(&self, season_id: u64) -> Pubkey {
+        self.pda(&[b"season", self.config.as_ref(), &season_id.to_le_bytes()])
+    }
+
+    pub fn leaderboard(&self, season_id: u64) -> Pubkey {
+        self.pda(&[b"leaderboard", self.config.as_ref(), &season_id.to_le_bytes()])
+    }
+
+    pub fn racer_stats(&self, racer: &Pubkey) -> Pubkey {
+        self.pda(&[b"racer_stats", self.config.as_ref(), racer.as_ref()])
+    }
+
+    pub fn season_stats(&self, racer: &Pubkey, season_id: u64) -> Pubkey {
+        self.pda(&[b"season_stats", self.config.as_ref(), racer.as_ref(), &season_id.to_le_bytes()])
+    }
+
+    pub fn config_state(&self) -> Config {
+        self.bank.state(&self.config)
+    }
+
+    /// Rewrites config in place, for settings whose own instructions are not under test
+    pub fn update_config(&mut self, update: impl FnOnce(&mut Config)) {
+        let mut config = self.config_state();
+        update(&mut config);
+        let account = self.bank.accounts.get_mut(&self.config).unwrap();
+        config.try_serialize(&mut &mut account.data[..]).unwrap();
+    }
+
+    /// Creates a token account of the mint, sized for its TransferFeeAmount
+    /// extension if it has one, minting `amount` into it (minting charges no fee)
+    pub fn token_account(&mut self, key: Pubkey, owner: Pubkey, amount: u64) {
+        let extensions = if self.transfer_fee { vec![ExtensionType::TransferFeeAmount] } else { Vec::new() };
+        let len = ExtensionType::try_calculate_account_len::<state::Account>(&extensions).unwrap();
+        self.bank.set(key, spl_token_2022::ID, vec![0; len]);
+        self.bank.process(&initialize_account3(&spl_token_2022::ID, &key, &self.mint, &owner).unwrap()).unwrap();
+        if amount > 0 {
+            self.mint_to(key, amount);
+        }
+    }
+
+    pub fn mint_to(&mut self, key: Pubkey, amount: u64) {
+        let mint_ix = mint_to(&spl_token_2022::ID, &self.mint, &key, &self.mint_authority, &[], amount).unwrap();
+        self.bank.process(&mint_ix).unwrap();
+    }
+
+    pub fn wallet(&mut self) -> Pubkey {
+        let wallet = Pubkey::new_unique();
+        self.bank.set(wallet, system_program::ID, Vec::new());
+        wallet
+    }
+
+    /// A wallet with its token account for the mint already created
+    pub fn racer(&mut self) -> Pubkey {
+        let racer = self.wallet();
+        self.token_account(self.ata(&racer), racer, 0);
+        racer
+    }
+
+    /// Creates and starts a race with `prize_budget`, returning its race_id_hash
+    pub fn start_race(&mut self, race_id: &str, prize_budget: u64) -> [u8; 32] {
+        let race_id_hash = anchor_lang::solana_program::hash::hash(race_id.as_bytes()).to_bytes();
+        let accounts = vault_program::accounts::CreateRace {
+            config: self.config,
+            operator: self.authority,
+            mint: self.mint,
+            prize_table: None,
+            race: self.race(&race_id_hash),
+            system_program: system_program::ID,
+        };
+        let data = vault_program::instruction::CreateRace {
+            race_id: race_id.to_string(),
+            race_id_hash,
+            prize_budget,
+            entry_fee: 0,
+        };
+        self.send(accounts, data).unwrap();
+
+        let accounts = vault_program::accounts::UpdateRace {
+            config: self.config,
+            operator: self.authority,
+            race: self.race(&race_id_hash),
+            global_entry_fee_registry: self.global_entry_fee_registry(),
+        };
+        self.send(accounts, vault_program::instruction::StartRace { _race_id_hash: race_id_hash }).unwrap();
+        race_id_hash
+    }
+
+    /// Opens season `season_id` over [start_at, end_at) with `budget` in the mint
+    pub fn open_season(&mut self, season_id: u64, start_at: i64, end_at: i64, budget: u64) {
+        let accounts = vault_program::accounts::OpenSeason {
+            config: self.config,
+            authority: self.authority,
+            mint: self.mint,
+            season: self.season(season_id),
+            leaderboard: self.leaderboard(season_id),
+            system_program: system_program::ID,
+        };
+        let data = vault_program::instruction::OpenSeason { season_id, start_at, end_at, budget };
+        self.send(accounts, data).unwrap();
+    }
+
+    /// Registers a payout for `recipient` on a started race, counting towards
+    /// the active season if there is one
+    pub fn register_payout(&mut self, race_id: &str, recipient: Pubkey, points: u64, amount: u64) -> ProgramResult {
+        let race_id_hash = anchor_lang::solana_program::hash::hash(race_id.as_bytes()).to_bytes();
+        let season_id = self.config_state().season_id;
+        let accounts = vault_program::accounts::RegisterPayout {
+            config: self.config,
+            operator: self.authority,
+            mint: self.mint,
+            recipient,
+            payout_receipt: self.receipt(&race_id_hash, &recipient),
+            race: self.race(&race_id_hash),
+            race_tombstone: self.pda(&[b"race_tombstone", self.config.as_ref(), &race_id_hash]),
+            payout_registry: self.payout_registry(&recipient),
+            racer_stats: self.racer_stats(&recipient),
+            season_stats: self.season_stats(&recipient, season_id),
+            global_payout_registry: self.global_payout_registry(),
+            season: (season_id != 0).then(|| self.season(season_id)),
+            leaderboard: (season_id != 0).then(|| self.leaderboard(season_id)),
+            global_referral_registry: self.global_referral_registry(),
+            global_entry_fee_registry: self.global_entry_fee_registry(),
+            vault_signer: self.vault_signer,
+            vault_token: self.vault_token,
+            system_program: system_program::ID,
+            token_program: spl_token_2022::ID,
+            rent: sysvar::rent::ID,
+        };
+        let data = vault_program::instruction::RegisterPayout {
+            race_id: race_id.to_string(),
+            race_id_hash,
+            points,
+            amount,
+        };
+        self.send(accounts, data)
+    }
+}
@@ -0,0 +1,242 @@
+//! Merkle race distributions: each leaf is claimed once against the posted root, and
+//! closing releases whatever was left unclaimed
+//!
+//! Instructions run in-process on the runtime in `common`
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{entrypoint::ProgramResult, hash::hashv};
+use anchor_lang::system_program;
+use anchor_spl::token_2022::spl_token_2022;
+use common::{vault_error, Vault, NOW};
+use vault_program::{GlobalPayoutRegistry, Race, RaceDistribution, RacerStats, Season, VaultError};
+
+const RACE_ID: &str = "race-1";
+const CLAIM_WINDOW: i64 = 3_600;
+
+#[derive(Clone, Copy)]
+struct Leaf {
+    recipient: Pubkey,
+    points: u64,
+    amount: u64,
+}
+
+fn leaf_hash(index: u32, leaf: &Leaf) -> [u8; 32] {
+    hashv(&[
+        &[0u8],
+        &index.to_le_bytes(),
+        leaf.recipient.as_ref(),
+        &leaf.points.to_le_bytes(),
+        &leaf.amount.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Root and per-leaf proofs of a sorted-pair tree over a power-of-two number of leaves
+fn merkle_tree(leaves: &[Leaf]) -> ([u8; 32], Vec<Vec<[u8; 32]>>) {
+    let mut level = leaves.iter().zip(0..).map(|(leaf, index)| leaf_hash(index, leaf)).collect::<Vec<_>>();
+    let mut proofs = vec![Vec::new(); leaves.len()];
+    let mut positions = (0..leaves.len()).collect::<Vec<_>>();
+    while level.len() > 1 {
+        for (proof, position) in proofs.iter_mut().zip(&mut positions) {
+            proof.push(level[*position ^ 1]);
+            *position /= 2;
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| hashv(&[&[1u8], &pair[0].min(pair[1]), &pair[0].max(pair[1])]).to_bytes())
+            .collect();
+    }
+    (level[0], proofs)
+}
+
+struct Distribution {
+    vault: Vault,
+    race_id_hash: [u8; 32],
+    leaves: Vec<Leaf>,
+    proofs: Vec<Vec<[u8; 32]>>,
+}
+
+impl Distribution {
+    /// Posts four leaves of 100..400 tokens on a started race, within season 1
+    fn post() -> Self {
+        let mut vault = Vault::new();
+        vault.update_config(|config| config.claim_window = CLAIM_WINDOW);
+        vault.mint_to(vault.vault_token, 10_000);
+        vault.open_season(1, NOW - 100, NOW + 10 * CLAIM_WINDOW, 5_000);
+        let race_id_hash = vault.start_race(RACE_ID, 2_000);
+
+        let leaves = (1..=4)
+            .map(|rank| {
+                let recipient = vault.racer();
+                Leaf { recipient, points: 10 * rank, amount: 100 * rank }
+            })
+            .collect::<Vec<_>>();
+        let (merkle_root, proofs) = merkle_tree(&leaves);
+        let accounts = vault_program::accounts::PostRaceDistribution {
+            config: vault.config,
+            operator: vault.authority,
+            mint: vault.mint,
+            race: vault.race(&race_id_hash),
+            race_distribution: vault.pda(&[b"race_distribution", vault.config.as_ref(), &race_id_hash]),
+            global_payout_registry: vault.global_payout_registry(),
+            season: Some(vault.season(1)),
+            global_referral_registry: vault.global_referral_registry(),
+            global_entry_fee_registry: vault.global_entry_fee_registry(),
+            vault_signer: vault.vault_signer,
+            vault_token: vault.vault_token,
+            system_program: system_program::ID,
+            token_program: spl_token_2022::ID,
+        };
+        let data = vault_program::instruction::PostRaceDistribution {
+            race_id: RACE_ID.to_string(),
+            race_id_hash,
+            merkle_root,
+            total_amount: 1_000,
+            leaf_count: 4,
+        };
+        vault.send(accounts, data).unwrap();
+        Distribution { vault, race_id_hash, leaves, proofs }
+    }
+
+    fn key(&self) -> Pubkey {
+        self.vault.pda(&[b"race_distribution", self.vault.config.as_ref(), &self.race_id_hash])
+    }
+
+    fn state(&self) -> RaceDistribution {
+        self.vault.bank.state(&self.key())
+    }
+
+    /// Claims leaf `index` as `leaf` with `proof`, whatever the tree holds
+    fn claim_as(&mut self, index: u32, leaf: Leaf, proof: Vec<[u8; 32]>) -> ProgramResult {
+        let payer = self.vault.wallet();
+        let vault = &self.vault;
+        let accounts = vault_program::accounts::ClaimDistribution {
+            config: vault.config,
+            vault_signer: vault.vault_signer,
+            mint: vault.mint,
+            vault_token: vault.vault_token,
+            race_distribution: self.key(),
+            recipient: leaf.recipient,
+            racer_stats: vault.racer_stats(&leaf.recipient),
+            season_stats: vault.season_stats(&leaf.recipient, 1),
+            leaderboard: Some(vault.leaderboard(1)),
+            recipient_token: vault.ata(&leaf.recipient),
+            global_payout_registry: vault.global_payout_registry(),
+            season: Some(vault.season(1)),
+            payer,
+            system_program: system_program::ID,
+            token_program: spl_token_2022::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+        };
+        let data = vault_program::instruction::ClaimDistribution {
+            index,
+            points: leaf.points,
+            amount: leaf.amount,
+            proof,
+        };
+        self.vault.send(accounts, data)
+    }
+
+    fn claim(&mut self, index: u32) -> ProgramResult {
+        let leaf = self.leaves[index as usize];
+        let proof = self.proofs[index as usize].clone();
+        self.claim_as(index, leaf, proof)
+    }
+
+    fn close(&mut self) -> ProgramResult {
+        let vault = &self.vault;
+        let accounts = vault_program::accounts::CloseRaceDistribution {
+            config: vault.config,
+            authority: vault.authority,
+            mint: vault.mint,
+            race_distribution: self.key(),
+            race: vault.race(&self.race_id_hash),
+            season: Some(vault.season(1)),
+            global_entry_fee_registry: vault.global_entry_fee_registry(),
+            global_payout_registry: vault.global_payout_registry(),
+        };
+        self.vault.send(accounts, vault_program::instruction::CloseRaceDistribution {})
+    }
+}
+
+#[test]
+fn valid_proof_pays_the_leaf() {
+    let mut distribution = Distribution::post();
+    distribution.claim(1).unwrap();
+
+    let recipient = distribution.leaves[1].recipient;
+    let vault = &distribution.vault;
+    assert_eq!(vault.bank.token_balance(&vault.ata(&recipient)).0, 200);
+    assert_eq!(vault.bank.token_balance(&vault.vault_token).0, 9_800);
+    let state = distribution.state();
+    assert_eq!((state.claimed_amount, state.claimed_count), (200, 1));
+    assert!(state.is_claimed(1) && !state.is_claimed(0));
+    let global: GlobalPayoutRegistry = vault.bank.state(&vault.global_payout_registry());
+    assert_eq!((global.total_pending, global.total_claimed), (800, 200));
+    let stats: RacerStats = vault.bank.state(&vault.season_stats(&recipient, 1));
+    assert_eq!((stats.season_id, stats.total_points), (1, 20));
+}
+
+#[test]
+fn tampered_leaf_or_proof_is_rejected() {
+    let mut distribution = Distribution::post();
+    let leaf = distribution.leaves[2];
+    let proof = distribution.proofs[2].clone();
+
+    let inflated = Leaf { amount: 1_000, ..leaf };
+    let result = distribution.claim_as(2, inflated, proof.clone());
+    assert_eq!(result, Err(vault_error(VaultError::InvalidMerkleProof)));
+
+    let redirected = Leaf { recipient: distribution.leaves[0].recipient, ..leaf };
+    let result = distribution.claim_as(2, redirected, proof.clone());
+    assert_eq!(result, Err(vault_error(VaultError::InvalidMerkleProof)));
+
+    // The leaf hash commits to its index
+    let result = distribution.claim_as(3, leaf, proof.clone());
+    assert_eq!(result, Err(vault_error(VaultError::InvalidMerkleProof)));
+
+    let mut forged = proof;
+    forged[0][0] ^= 1;
+    let result = distribution.claim_as(2, leaf, forged);
+    assert_eq!(result, Err(vault_error(VaultError::InvalidMerkleProof)));
+
+    assert_eq!(distribution.state().claimed_count, 0);
+}
+
+#[test]
+fn leaf_cannot_be_claimed_twice() {
+    let mut distribution = Distribution::post();
+    distribution.claim(0).unwrap();
+    assert_eq!(distribution.claim(0), Err(vault_error(VaultError::DistributionAlreadyClaimed)));
+
+    let recipient = distribution.leaves[0].recipient;
+    let vault = &distribution.vault;
+    assert_eq!(vault.bank.token_balance(&vault.ata(&recipient)).0, 100);
+    assert_eq!(distribution.state().claimed_amount, 100);
+}
+
+#[test]
+fn close_releases_the_unclaimed_rest() {
+    let mut distribution = Distribution::post();
+    distribution.claim(0).unwrap();
+    distribution.claim(3).unwrap();
+    assert_eq!(distribution.close(), Err(vault_error(VaultError::DistributionNotExpired)));
+
+    common::warp(NOW + CLAIM_WINDOW);
+    let authority_lamports = distribution.vault.bank.accounts[&distribution.vault.authority].lamports;
+    distribution.close().unwrap();
+
+    // Leaves 1 and 2 (500 tokens) were never claimed
+    let vault = &distribution.vault;
+    assert!(!vault.bank.exists(&distribution.key()));
+    assert!(vault.bank.accounts[&vault.authority].lamports > authority_lamports);
+    let global: GlobalPayoutRegistry = vault.bank.state(&vault.global_payout_registry());
+    assert_eq!((global.total_pending, global.total_claimed), (0, 500));
+    let race: Race = vault.bank.state(&vault.race(&distribution.race_id_hash));
+    assert_eq!((race.settled_total, race.finisher_count), (500, 2));
+    let season: Season = vault.bank.state(&vault.season(1));
+    assert_eq!((season.total_registered, season.payout_count), (500, 2));
+    assert_eq!(vault.bank.token_balance(&vault.vault_token).0, 9_500);
+}
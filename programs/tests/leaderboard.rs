@@ -0,0 +1,47 @@
+//! Leaderboard ordering as season points rise and fall
+
+use anchor_lang::prelude::*;
+use bytemuck::Zeroable;
+use vault_program::Leaderboard;
+
+fn standings(board: &Leaderboard) -> Vec<(Pubkey, u64)> {
+    board.entries[..board.len as usize].iter().map(|entry| (entry.racer, entry.points)).collect()
+}
+
+#[test]
+fn raised_racer_moves_ahead() {
+    let mut board = Leaderboard::zeroed();
+    let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    board.record(a, 30);
+    board.record(b, 20);
+    board.record(c, 10);
+    board.record(c, 25);
+    assert_eq!(standings(&board), vec![(a, 30), (c, 25), (b, 20)]);
+}
+
+#[test]
+fn lowered_racer_falls_behind_level_racers() {
+    let mut board = Leaderboard::zeroed();
+    let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    board.record(a, 30);
+    board.record(b, 20);
+    board.record(c, 10);
+    board.record(a, 20);
+    assert_eq!(standings(&board), vec![(b, 20), (a, 20), (c, 10)]);
+    board.record(a, 5);
+    assert_eq!(standings(&board), vec![(b, 20), (c, 10), (a, 5)]);
+}
+
+#[test]
+fn racer_at_zero_points_leaves_the_board() {
+    let mut board = Leaderboard::zeroed();
+    let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    board.record(a, 30);
+    board.record(b, 20);
+    board.record(c, 10);
+    board.record(a, 0);
+    assert_eq!(standings(&board), vec![(b, 20), (c, 10)]);
+    assert_eq!(board.entries[2].points, 0);
+    board.record(Pubkey::new_unique(), 0);
+    assert_eq!(board.len, 2);
+}
@@ -0,0 +1,280 @@
+//! Registered payouts: the points ledgers they feed, and how cancelling, amending
+//! and sweeping them keep every registry, race, season and points ledger in step
+//!
+//! Instructions run in-process on the runtime in `common`
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{entrypoint::ProgramResult, hash::hash, sysvar};
+use anchor_lang::system_program;
+use anchor_spl::token_2022::spl_token_2022;
+use common::{vault_error, Vault, NOW};
+use vault_program::{
+    GlobalPayoutRegistry, Leaderboard, PayoutReceipt, PayoutRegistry, PayoutStatus, Race, RacerStats, Season,
+    VaultError,
+};
+
+/// A funded vault with a started race "race-1" and, if `season` is given, season 1
+/// registering over that window
+fn payout_vault(season: Option<(i64, i64)>) -> Vault {
+    let mut vault = Vault::new();
+    vault.mint_to(vault.vault_token, 10_000);
+    if let Some((start_at, end_at)) = season {
+        vault.open_season(1, start_at, end_at, 5_000);
+    }
+    vault.start_race("race-1", 1_000);
+    vault
+}
+
+fn leaderboard(vault: &Vault, season_id: u64) -> Leaderboard {
+    let data = &vault.bank.accounts[&vault.leaderboard(season_id)].data;
+    *bytemuck::from_bytes(&data[8..8 + Leaderboard::SIZE])
+}
+
+fn race_id_hash(race_id: &str) -> [u8; 32] {
+    hash(race_id.as_bytes()).to_bytes()
+}
+
+/// Cancels the recipient's race-1 payout, passing the race account only if `with_race`
+fn cancel(vault: &mut Vault, recipient: Pubkey, with_race: bool) -> ProgramResult {
+    let race_id_hash = race_id_hash("race-1");
+    let receipt: PayoutReceipt = vault.bank.state(&vault.receipt(&race_id_hash, &recipient));
+    let accounts = vault_program::accounts::CancelPayout {
+        config: vault.config,
+        authority: vault.authority,
+        mint: vault.mint,
+        recipient,
+        payout_receipt: vault.receipt(&race_id_hash, &recipient),
+        race: with_race.then(|| vault.race(&race_id_hash)),
+        season: Some(vault.season(receipt.season_id)),
+        racer_stats: Some(vault.racer_stats(&recipient)),
+        season_stats: Some(vault.season_stats(&recipient, receipt.season_id)),
+        leaderboard: Some(vault.leaderboard(1)),
+        global_entry_fee_registry: vault.global_entry_fee_registry(),
+        payout_registry: vault.payout_registry(&recipient),
+        global_payout_registry: vault.global_payout_registry(),
+    };
+    vault.send(accounts, vault_program::instruction::CancelPayout { race_id_hash })
+}
+
+/// Amends the recipient's race-1 payout, passing the race account only if `with_race`
+fn amend(vault: &mut Vault, recipient: Pubkey, new_points: u64, new_amount: u64, with_race: bool) -> ProgramResult {
+    let race_id_hash = race_id_hash("race-1");
+    let receipt: PayoutReceipt = vault.bank.state(&vault.receipt(&race_id_hash, &recipient));
+    let accounts = vault_program::accounts::AmendPayout {
+        config: vault.config,
+        authority: vault.authority,
+        mint: vault.mint,
+        recipient,
+        payout_receipt: vault.receipt(&race_id_hash, &recipient),
+        race: with_race.then(|| vault.race(&race_id_hash)),
+        season: Some(vault.season(receipt.season_id)),
+        racer_stats: Some(vault.racer_stats(&recipient)),
+        season_stats: Some(vault.season_stats(&recipient, receipt.season_id)),
+        leaderboard: Some(vault.leaderboard(1)),
+        payout_registry: vault.payout_registry(&recipient),
+        global_payout_registry: vault.global_payout_registry(),
+        global_referral_registry: vault.global_referral_registry(),
+        global_entry_fee_registry: vault.global_entry_fee_registry(),
+        vault_signer: vault.vault_signer,
+        vault_token: vault.vault_token,
+        token_program: spl_token_2022::ID,
+    };
+    let data = vault_program::instruction::AmendPayout { race_id_hash, new_points, new_amount };
+    vault.send(accounts, data)
+}
+
+/// Sweeps the recipient's expired race-1 payout, passing the race account only if `with_race`
+fn sweep(vault: &mut Vault, recipient: Pubkey, with_race: bool) -> ProgramResult {
+    let race_id_hash = race_id_hash("race-1");
+    let receipt: PayoutReceipt = vault.bank.state(&vault.receipt(&race_id_hash, &recipient));
+    let accounts = vault_program::accounts::SweepExpiredPayout {
+        config: vault.config,
+        mint: vault.mint,
+        recipient,
+        payout_receipt: vault.receipt(&race_id_hash, &recipient),
+        race: with_race.then(|| vault.race(&race_id_hash)),
+        season: Some(vault.season(receipt.season_id)),
+        global_entry_fee_registry: vault.global_entry_fee_registry(),
+        payout_registry: vault.payout_registry(&recipient),
+        global_payout_registry: vault.global_payout_registry(),
+    };
+    vault.send(accounts, vault_program::instruction::SweepExpiredPayout { race_id_hash })
+}
+
+fn claim(vault: &mut Vault, recipient: Pubkey) -> ProgramResult {
+    let payer = vault.wallet();
+    let accounts = vault_program::accounts::ClaimPendingPayouts {
+        config: vault.config,
+        vault_signer: vault.vault_signer,
+        mint: vault.mint,
+        vault_token: vault.vault_token,
+        recipient,
+        recipient_token: vault.ata(&recipient),
+        payout_registry: vault.payout_registry(&recipient),
+        global_payout_registry: vault.global_payout_registry(),
+        season: Some(vault.season(1)),
+        payer,
+        system_program: system_program::ID,
+        token_program: spl_token_2022::ID,
+        associated_token_program: anchor_spl::associated_token::ID,
+        rent: sysvar::rent::ID,
+    };
+    vault.send(accounts, vault_program::instruction::ClaimPendingPayouts { recipient })
+}
+
+/// Pending amount and season pending amount in the recipient's registry
+fn pending(vault: &Vault, recipient: &Pubkey) -> (u64, u64) {
+    let registry: PayoutRegistry = vault.bank.state(&vault.payout_registry(recipient));
+    (registry.total_pending, registry.season_pending)
+}
+
+/// Lifetime and season points of the racer, with their race counts
+fn points(vault: &Vault, racer: &Pubkey) -> ((u64, u32), (u64, u32)) {
+    let lifetime: RacerStats = vault.bank.state(&vault.racer_stats(racer));
+    let season: RacerStats = vault.bank.state(&vault.season_stats(racer, 1));
+    ((lifetime.total_points, lifetime.race_count), (season.total_points, season.race_count))
+}
+
+/// Settled total and finisher count of race-1
+fn race_totals(vault: &Vault) -> (u64, u32) {
+    let race: Race = vault.bank.state(&vault.race(&race_id_hash("race-1")));
+    (race.settled_total, race.finisher_count)
+}
+
+/// Registered total and payout count of season 1
+fn season_totals(vault: &Vault) -> (u64, u32) {
+    let season: Season = vault.bank.state(&vault.season(1));
+    (season.total_registered, season.payout_count)
+}
+
+fn global_pending(vault: &Vault) -> u64 {
+    vault.bank.state::<GlobalPayoutRegistry>(&vault.global_payout_registry()).total_pending
+}
+
+fn ranking(vault: &Vault) -> Vec<(Pubkey, u64)> {
+    let board = leaderboard(vault, 1);
+    board.entries[..board.len as usize].iter().map(|entry| (entry.racer, entry.points)).collect()
+}
+
+#[test]
+fn registration_outside_a_season_creates_no_season_stats() {
+    let mut vault = payout_vault(None);
+    let racer = vault.wallet();
+    vault.register_payout("race-1", racer, 10, 100).unwrap();
+
+    let stats: RacerStats = vault.bank.state(&vault.racer_stats(&racer));
+    assert_eq!((stats.total_points, stats.race_count), (10, 1));
+    assert!(!vault.bank.exists(&vault.season_stats(&racer, 0)));
+}
+
+#[test]
+fn registration_before_the_season_window_creates_no_season_stats() {
+    let mut vault = payout_vault(Some((NOW + 100, NOW + 1_000)));
+    let racer = vault.wallet();
+    vault.register_payout("race-1", racer, 10, 100).unwrap();
+
+    assert!(!vault.bank.exists(&vault.season_stats(&racer, 1)));
+    assert_eq!(leaderboard(&vault, 1).len, 0);
+}
+
+#[test]
+fn registration_in_a_season_records_season_points() {
+    let mut vault = payout_vault(Some((NOW - 100, NOW + 1_000)));
+    let racer = vault.wallet();
+    vault.register_payout("race-1", racer, 10, 100).unwrap();
+
+    let stats: RacerStats = vault.bank.state(&vault.season_stats(&racer, 1));
+    assert_eq!((stats.racer, stats.season_id, stats.total_points), (racer, 1, 10));
+    let board = leaderboard(&vault, 1);
+    assert_eq!((board.len, board.entries[0].racer, board.entries[0].points), (1, racer, 10));
+}
+
+#[test]
+fn amend_and_cancel_keep_season_ledgers_in_step() {
+    let mut vault = payout_vault(Some((NOW - 100, NOW + 1_000)));
+    let (first, second) = (vault.racer(), vault.racer());
+    vault.register_payout("race-1", first, 30, 300).unwrap();
+    vault.register_payout("race-1", second, 20, 200).unwrap();
+    assert_eq!(ranking(&vault), vec![(first, 30), (second, 20)]);
+
+    amend(&mut vault, first, 10, 100, true).unwrap();
+    let receipt: PayoutReceipt = vault.bank.state(&vault.receipt(&race_id_hash("race-1"), &first));
+    assert_eq!((receipt.status, receipt.points, receipt.amount), (PayoutStatus::Amended, 10, 100));
+    assert_eq!(pending(&vault, &first), (100, 100));
+    assert_eq!(global_pending(&vault), 300);
+    assert_eq!(race_totals(&vault), (300, 2));
+    assert_eq!(season_totals(&vault), (300, 2));
+    assert_eq!(points(&vault, &first), ((10, 1), (10, 1)));
+    assert_eq!(ranking(&vault), vec![(second, 20), (first, 10)]);
+
+    cancel(&mut vault, second, true).unwrap();
+    let receipt: PayoutReceipt = vault.bank.state(&vault.receipt(&race_id_hash("race-1"), &second));
+    assert_eq!(receipt.status, PayoutStatus::Cancelled);
+    assert_eq!(pending(&vault, &second), (0, 0));
+    assert_eq!(global_pending(&vault), 100);
+    assert_eq!(race_totals(&vault), (100, 1));
+    assert_eq!(season_totals(&vault), (100, 1));
+    assert_eq!(points(&vault, &second), ((0, 0), (0, 0)));
+    assert_eq!(ranking(&vault), vec![(first, 10)]);
+
+    // A cancelled payout can neither be amended nor cancelled again
+    assert_eq!(amend(&mut vault, second, 5, 50, true), Err(vault_error(VaultError::PayoutCancelled)));
+    assert_eq!(cancel(&mut vault, second, true), Err(vault_error(VaultError::PayoutCancelled)));
+}
+
+#[test]
+fn race_payouts_need_their_race_account() {
+    let mut vault = payout_vault(Some((NOW - 100, NOW + 1_000)));
+    let racer = vault.racer();
+    vault.register_payout("race-1", racer, 30, 300).unwrap();
+
+    assert_eq!(cancel(&mut vault, racer, false), Err(vault_error(VaultError::RaceAccountMismatch)));
+    assert_eq!(amend(&mut vault, racer, 10, 100, false), Err(vault_error(VaultError::RaceAccountMismatch)));
+    assert_eq!(pending(&vault, &racer), (300, 300));
+    assert_eq!(race_totals(&vault), (300, 1));
+}
+
+#[test]
+fn claimed_payouts_cannot_be_amended_or_cancelled() {
+    let mut vault = payout_vault(Some((NOW - 100, NOW + 1_000)));
+    let racer = vault.racer();
+    vault.register_payout("race-1", racer, 30, 300).unwrap();
+    claim(&mut vault, racer).unwrap();
+
+    assert_eq!(amend(&mut vault, racer, 10, 100, true), Err(vault_error(VaultError::PayoutAlreadyClaimed)));
+    assert_eq!(cancel(&mut vault, racer, true), Err(vault_error(VaultError::PayoutAlreadyClaimed)));
+    assert_eq!(race_totals(&vault), (300, 1));
+    assert_eq!(season_totals(&vault), (300, 1));
+    assert_eq!(points(&vault, &racer), ((30, 1), (30, 1)));
+    assert_eq!(vault.bank.token_balance(&vault.ata(&racer)).0, 300);
+}
+
+#[test]
+fn sweep_releases_race_and_season_budget_but_keeps_points() {
+    let mut vault = payout_vault(Some((NOW - 100, NOW + 10_000)));
+    vault.update_config(|config| config.claim_window = 3_600);
+    let racer = vault.racer();
+    vault.register_payout("race-1", racer, 30, 300).unwrap();
+    assert_eq!(sweep(&mut vault, racer, true), Err(vault_error(VaultError::PayoutNotExpired)));
+
+    common::warp(NOW + 3_600);
+    assert_eq!(sweep(&mut vault, racer, false), Err(vault_error(VaultError::RaceAccountMismatch)));
+    sweep(&mut vault, racer, true).unwrap();
+
+    let receipt: PayoutReceipt = vault.bank.state(&vault.receipt(&race_id_hash("race-1"), &racer));
+    assert_eq!(receipt.status, PayoutStatus::Expired);
+    assert_eq!(pending(&vault, &racer), (0, 0));
+    assert_eq!(global_pending(&vault), 0);
+    assert_eq!(race_totals(&vault), (0, 0));
+    assert_eq!(season_totals(&vault), (0, 0));
+    assert_eq!(points(&vault, &racer), ((30, 1), (30, 1)));
+    assert_eq!(ranking(&vault), vec![(racer, 30)]);
+
+    // The released budget can be registered again
+    let other = vault.racer();
+    vault.register_payout("race-1", other, 10, 1_000).unwrap();
+    assert_eq!(race_totals(&vault), (1_000, 1));
+    assert_eq!(sweep(&mut vault, racer, true), Err(vault_error(VaultError::PayoutExpired)));
+}
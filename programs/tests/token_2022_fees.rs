@@ -0,0 +1,193 @@
+//! Token-2022 transfer-fee mints: deposits, claims and surplus withdrawals book what
+//! actually moves, so the fee never leaves the vault short of its obligations
+//!
+//! Instructions run in-process on the runtime in `common`
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{entrypoint::ProgramResult, sysvar};
+use anchor_lang::{system_program, InstructionData};
+use anchor_spl::token_2022::spl_token_2022;
+use common::{vault_error, Vault, NOW};
+use vault_program::{DepositorLedger, GlobalPayoutRegistry, PayoutRegistry, VaultError};
+
+const FEE_BPS: u16 = 100;  // 1% of every transfer is withheld
+
+/// The instructions under test, on a vault whose mint withholds FEE_BPS
+trait FeeVault {
+    fn deposit(&mut self, depositor: Pubkey, amount: u64) -> ProgramResult;
+    fn deposit_with_memo(&mut self, depositor: Pubkey, amount: u64, memo: &str) -> ProgramResult;
+    fn deposit_with(&mut self, depositor: Pubkey, data: impl InstructionData) -> ProgramResult;
+    fn register_pending(&mut self, recipient: Pubkey, amount: u64);
+    fn claim_pending_payouts(&mut self, recipient: Pubkey) -> ProgramResult;
+    fn withdraw_surplus(&mut self, destination_token: Pubkey, amount: u64) -> ProgramResult;
+}
+
+impl FeeVault for Vault {
+    fn deposit(&mut self, depositor: Pubkey, amount: u64) -> ProgramResult {
+        self.deposit_with(depositor, vault_program::instruction::Deposit { amount })
+    }
+
+    fn deposit_with_memo(&mut self, depositor: Pubkey, amount: u64, memo: &str) -> ProgramResult {
+        let data = vault_program::instruction::DepositWithMemo { amount, memo: memo.to_string() };
+        self.deposit_with(depositor, data)
+    }
+
+    fn deposit_with(&mut self, depositor: Pubkey, data: impl InstructionData) -> ProgramResult {
+        let accounts = vault_program::accounts::Deposit {
+            config: self.config,
+            vault_signer: self.vault_signer,
+            mint: self.mint,
+            vault_token: self.vault_token,
+            depositor,
+            depositor_token: self.ata(&depositor),
+            depositor_ledger: self.pda(&[b"depositor_ledger", self.config.as_ref(), depositor.as_ref()]),
+            system_program: system_program::ID,
+            token_program: spl_token_2022::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+        };
+        self.send(accounts, data)
+    }
+
+    /// Books `amount` as pending for `recipient`, the state register_payout leaves behind
+    fn register_pending(&mut self, recipient: Pubkey, amount: u64) {
+        let registry = PayoutRegistry {
+            recipient,
+            total_pending: amount,
+            total_claimed: 0,
+            payout_count: 1,
+            last_updated: NOW,
+            claimed_count: 0,
+            season_id: 0,
+            season_pending: 0,
+        };
+        self.bank.set_state(self.payout_registry(&recipient), &registry, 8 + PayoutRegistry::SIZE);
+
+        let global = GlobalPayoutRegistry {
+            total_pending: amount,
+            total_claimed: 0,
+            total_payout_count: 1,
+            total_recipient_count: 1,
+            last_updated: NOW,
+        };
+        self.bank.set_state(self.global_payout_registry(), &global, 8 + GlobalPayoutRegistry::SIZE);
+    }
+
+    fn claim_pending_payouts(&mut self, recipient: Pubkey) -> ProgramResult {
+        let payer = self.wallet();
+        let accounts = vault_program::accounts::ClaimPendingPayouts {
+            config: self.config,
+            vault_signer: self.vault_signer,
+            mint: self.mint,
+            vault_token: self.vault_token,
+            recipient,
+            recipient_token: self.ata(&recipient),
+            payout_registry: self.payout_registry(&recipient),
+            global_payout_registry: self.global_payout_registry(),
+            season: None,
+            payer,
+            system_program: system_program::ID,
+            token_program: spl_token_2022::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            rent: sysvar::rent::ID,
+        };
+        self.send(accounts, vault_program::instruction::ClaimPendingPayouts { recipient })
+    }
+
+    fn withdraw_surplus(&mut self, destination_token: Pubkey, amount: u64) -> ProgramResult {
+        let accounts = vault_program::accounts::WithdrawSurplus {
+            config: self.config,
+            treasurer: self.authority,
+            vault_signer: self.vault_signer,
+            mint: self.mint,
+            vault_token: self.vault_token,
+            destination_token,
+            global_payout_registry: self.global_payout_registry(),
+            global_referral_registry: self.global_referral_registry(),
+            global_entry_fee_registry: self.global_entry_fee_registry(),
+            token_program: spl_token_2022::ID,
+        };
+        self.send(accounts, vault_program::instruction::WithdrawSurplus { amount })
+    }
+}
+
+#[test]
+fn deposit_credits_amount_net_of_fee() {
+    let mut vault = Vault::with_transfer_fee(FEE_BPS);
+    let depositor = vault.wallet();
+    vault.token_account(vault.ata(&depositor), depositor, 1_000_000);
+
+    vault.deposit(depositor, 100_000).unwrap();
+    vault.deposit_with_memo(depositor, 50_000, "title sponsor").unwrap();
+
+    let ledger_key = vault.pda(&[b"depositor_ledger", vault.config.as_ref(), depositor.as_ref()]);
+    let ledger: DepositorLedger = vault.bank.state(&ledger_key);
+    assert_eq!(ledger.total_deposited, 148_500);
+    assert_eq!(ledger.deposit_count, 2);
+    assert_eq!(vault.bank.token_balance(&vault.vault_token), (148_500, 1_500));
+    assert_eq!(vault.bank.token_balance(&vault.ata(&depositor)).0, 850_000);
+}
+
+#[test]
+fn claim_settles_full_pending_and_recipient_bears_fee() {
+    let mut vault = Vault::with_transfer_fee(FEE_BPS);
+    let depositor = vault.wallet();
+    vault.token_account(vault.ata(&depositor), depositor, 1_000_000);
+    vault.deposit(depositor, 100_000).unwrap();
+
+    let recipient = vault.wallet();
+    vault.token_account(vault.ata(&recipient), recipient, 0);
+    vault.register_pending(recipient, 99_000);
+    vault.claim_pending_payouts(recipient).unwrap();
+
+    let registry_key = vault.pda(&[b"payout_registry", vault.config.as_ref(), recipient.as_ref()]);
+    let registry: PayoutRegistry = vault.bank.state(&registry_key);
+    assert_eq!((registry.total_pending, registry.total_claimed, registry.claimed_count), (0, 99_000, 1));
+    let global_key = vault.pda(&[b"global_payout_registry", vault.config.as_ref()]);
+    let global: GlobalPayoutRegistry = vault.bank.state(&global_key);
+    assert_eq!((global.total_pending, global.total_claimed), (0, 99_000));
+
+    // The vault pays out the whole payout; the fee comes out of what the recipient receives
+    assert_eq!(vault.bank.token_balance(&vault.vault_token).0, 0);
+    assert_eq!(vault.bank.token_balance(&vault.ata(&recipient)), (98_010, 990));
+}
+
+#[test]
+fn claim_fails_when_vault_only_holds_net_deposits() {
+    let mut vault = Vault::with_transfer_fee(FEE_BPS);
+    let depositor = vault.wallet();
+    vault.token_account(vault.ata(&depositor), depositor, 1_000_000);
+    vault.deposit(depositor, 100_000).unwrap();
+
+    // Booking the gross deposit as a payout overcommits the vault by the fee
+    let recipient = vault.wallet();
+    vault.token_account(vault.ata(&recipient), recipient, 0);
+    vault.register_pending(recipient, 100_000);
+    assert_eq!(vault.claim_pending_payouts(recipient), Err(vault_error(VaultError::InsufficientBalance)));
+}
+
+#[test]
+fn withdraw_keeps_pending_payouts_funded() {
+    let mut vault = Vault::with_transfer_fee(FEE_BPS);
+    let depositor = vault.wallet();
+    vault.token_account(vault.ata(&depositor), depositor, 1_000_000);
+    vault.deposit(depositor, 100_000).unwrap();
+
+    let recipient = vault.wallet();
+    vault.token_account(vault.ata(&recipient), recipient, 0);
+    vault.register_pending(recipient, 60_000);
+    let treasury = vault.wallet();
+    let treasury_token = vault.ata(&treasury);
+    vault.token_account(treasury_token, treasury, 0);
+
+    // Surplus is the net balance above what is pending, not the gross deposit
+    assert_eq!(vault.withdraw_surplus(treasury_token, 40_000), Err(vault_error(VaultError::InsufficientSurplus)));
+    vault.withdraw_surplus(treasury_token, 39_000).unwrap();
+    assert_eq!(vault.bank.token_balance(&vault.vault_token).0, 60_000);
+    assert_eq!(vault.bank.token_balance(&treasury_token), (38_610, 390));
+
+    vault.claim_pending_payouts(recipient).unwrap();
+    assert_eq!(vault.bank.token_balance(&vault.vault_token).0, 0);
+    assert_eq!(vault.bank.token_balance(&vault.ata(&recipient)).0, 59_400);
+}
@@ -0,0 +1,182 @@
+//! claim_with_voucher: an off-chain signed voucher pays out once, keyed by its nonce
+//!
+//! The ed25519 precompile instruction is only laid out, not signed; the program
+//! checks who signed what and leaves the signature itself to the runtime
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{entrypoint::ProgramResult, program_error::ProgramError, sysvar};
+use anchor_lang::system_program;
+use anchor_spl::token_2022::spl_token_2022;
+use common::{ed25519_instruction, vault_error, Vault};
+use vault_program::{voucher_message, RacerStats, VaultError, VoucherClaim};
+
+#[derive(Clone, Copy)]
+struct Voucher {
+    race_id_hash: [u8; 32],
+    recipient: Pubkey,
+    points: u64,
+    amount: u64,
+    expiry: i64,
+    nonce: u64,
+}
+
+/// A vault holding `balance` whose vouchers `signer` signs
+fn voucher_vault(balance: u64) -> (Vault, Pubkey) {
+    let mut vault = Vault::new();
+    let signer = Pubkey::new_unique();
+    vault.update_config(|config| config.voucher_signer = signer);
+    vault.mint_to(vault.vault_token, balance);
+    (vault, signer)
+}
+
+fn message(vault: &Vault, voucher: &Voucher) -> Vec<u8> {
+    voucher_message(
+        &vault.config,
+        &vault.mint,
+        &voucher.race_id_hash,
+        &voucher.recipient,
+        voucher.points,
+        voucher.amount,
+        voucher.expiry,
+        voucher.nonce,
+    )
+}
+
+fn voucher_claim(vault: &Vault, nonce: u64) -> Pubkey {
+    vault.pda(&[b"voucher", vault.config.as_ref(), &nonce.to_le_bytes()])
+}
+
+/// Redeems `voucher` right after an ed25519 check of `signed_message` by `signer`
+fn claim(vault: &mut Vault, voucher: &Voucher, signer: &Pubkey, signed_message: &[u8]) -> ProgramResult {
+    let recipient = voucher.recipient;
+    let accounts = vault_program::accounts::ClaimWithVoucher {
+        config: vault.config,
+        vault_signer: vault.vault_signer,
+        mint: vault.mint,
+        vault_token: vault.vault_token,
+        recipient,
+        recipient_token: vault.ata(&recipient),
+        voucher_claim: voucher_claim(vault, voucher.nonce),
+        global_payout_registry: vault.global_payout_registry(),
+        season: None,
+        leaderboard: None,
+        racer_stats: vault.racer_stats(&recipient),
+        season_stats: vault.season_stats(&recipient, 0),
+        global_referral_registry: vault.global_referral_registry(),
+        global_entry_fee_registry: vault.global_entry_fee_registry(),
+        instructions: sysvar::instructions::ID,
+        system_program: system_program::ID,
+        token_program: spl_token_2022::ID,
+        associated_token_program: anchor_spl::associated_token::ID,
+    };
+    let data = vault_program::instruction::ClaimWithVoucher {
+        race_id_hash: voucher.race_id_hash,
+        points: voucher.points,
+        amount: voucher.amount,
+        expiry: voucher.expiry,
+        nonce: voucher.nonce,
+    };
+    let instruction = vault.instruction(accounts, data);
+    vault.bank.set_instructions(&[ed25519_instruction(signer, signed_message), instruction.clone()], 1);
+    vault.bank.process(&instruction)
+}
+
+#[test]
+fn voucher_claims_are_keyed_by_nonce() {
+    let (mut vault, signer) = voucher_vault(1_000);
+    let recipient = vault.racer();
+    let expiry = common::NOW + 3_600;
+
+    // Same race, recipient and expiry; only the nonce tells the vouchers apart
+    for nonce in [7, 8] {
+        let voucher = Voucher { race_id_hash: [1; 32], recipient, points: 10, amount: 100, expiry, nonce };
+        let signed = message(&vault, &voucher);
+        claim(&mut vault, &voucher, &signer, &signed).unwrap();
+
+        let record: VoucherClaim = vault.bank.state(&voucher_claim(&vault, nonce));
+        assert_eq!((record.recipient, record.amount), (recipient, 100));
+    }
+    assert!(!vault.bank.exists(&voucher_claim(&vault, expiry as u64)));
+    assert_eq!(vault.bank.token_balance(&vault.ata(&recipient)).0, 200);
+    assert_eq!(vault.bank.token_balance(&vault.vault_token).0, 800);
+}
+
+#[test]
+fn valid_voucher_pays_and_records_points() {
+    let (mut vault, signer) = voucher_vault(1_000);
+    let recipient = vault.racer();
+    let voucher = Voucher { race_id_hash: [1; 32], recipient, points: 25, amount: 250, expiry: common::NOW, nonce: 1 };
+    let signed = message(&vault, &voucher);
+    claim(&mut vault, &voucher, &signer, &signed).unwrap();
+
+    let record: VoucherClaim = vault.bank.state(&voucher_claim(&vault, 1));
+    assert_eq!(record.race_id_hash, [1; 32]);
+    assert_eq!((record.mint, record.claimed_at), (vault.mint, common::NOW));
+    let stats: RacerStats = vault.bank.state(&vault.racer_stats(&recipient));
+    assert_eq!((stats.total_points, stats.race_count), (25, 1));
+    assert!(!vault.bank.exists(&vault.season_stats(&recipient, 0)));
+    assert_eq!(vault.bank.token_balance(&vault.ata(&recipient)).0, 250);
+}
+
+#[test]
+fn voucher_signed_by_another_key_is_rejected() {
+    let (mut vault, _) = voucher_vault(1_000);
+    let recipient = vault.racer();
+    let voucher = Voucher { race_id_hash: [1; 32], recipient, points: 25, amount: 250, expiry: common::NOW, nonce: 1 };
+    let signed = message(&vault, &voucher);
+    let result = claim(&mut vault, &voucher, &Pubkey::new_unique(), &signed);
+    assert_eq!(result, Err(vault_error(VaultError::InvalidVoucherSignature)));
+    assert!(!vault.bank.exists(&voucher_claim(&vault, 1)));
+}
+
+#[test]
+fn voucher_for_other_terms_is_rejected() {
+    let (mut vault, signer) = voucher_vault(1_000);
+    let recipient = vault.racer();
+    let voucher = Voucher { race_id_hash: [1; 32], recipient, points: 25, amount: 250, expiry: common::NOW, nonce: 1 };
+
+    // Signed for 25 tokens, redeemed for 250
+    let signed = message(&vault, &Voucher { amount: 25, ..voucher });
+    let result = claim(&mut vault, &voucher, &signer, &signed);
+    assert_eq!(result, Err(vault_error(VaultError::InvalidVoucherSignature)));
+
+    // Signed for someone else
+    let signed = message(&vault, &Voucher { recipient: Pubkey::new_unique(), ..voucher });
+    let result = claim(&mut vault, &voucher, &signer, &signed);
+    assert_eq!(result, Err(vault_error(VaultError::InvalidVoucherSignature)));
+    assert_eq!(vault.bank.token_balance(&vault.vault_token).0, 1_000);
+}
+
+#[test]
+fn expired_voucher_is_rejected() {
+    let (mut vault, signer) = voucher_vault(1_000);
+    let recipient = vault.racer();
+    let voucher = Voucher { race_id_hash: [1; 32], recipient, points: 25, amount: 250, expiry: common::NOW, nonce: 1 };
+    let signed = message(&vault, &voucher);
+
+    common::warp(common::NOW + 1);
+    let result = claim(&mut vault, &voucher, &signer, &signed);
+    assert_eq!(result, Err(vault_error(VaultError::VoucherExpired)));
+    assert!(!vault.bank.exists(&voucher_claim(&vault, 1)));
+}
+
+#[test]
+fn voucher_nonce_cannot_be_replayed() {
+    let (mut vault, signer) = voucher_vault(1_000);
+    let recipient = vault.racer();
+    let voucher = Voucher { race_id_hash: [1; 32], recipient, points: 25, amount: 250, expiry: common::NOW, nonce: 1 };
+    let signed = message(&vault, &voucher);
+    claim(&mut vault, &voucher, &signer, &signed).unwrap();
+
+    // The nonce record already exists, so creating it again fails in the system program
+    let result = claim(&mut vault, &voucher, &signer, &signed);
+    assert_eq!(result, Err(ProgramError::Custom(0)));
+
+    // A different voucher reusing the nonce is refused the same way
+    let other = Voucher { race_id_hash: [2; 32], ..voucher };
+    let signed = message(&vault, &other);
+    assert_eq!(claim(&mut vault, &other, &signer, &signed), Err(ProgramError::Custom(0)));
+    assert_eq!(vault.bank.token_balance(&vault.ata(&recipient)).0, 250);
+}